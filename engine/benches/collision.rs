@@ -0,0 +1,83 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use engine::collider::{BroadPhase, BroadPhaseSystem, CollisionSystem, DEFAULT_CELL_SIZE};
+use engine::math::Vec2;
+use engine::prelude::*;
+use engine::Game;
+
+const WORLD: u32 = 1 << 1;
+const PLAYER: u32 = 1 << 2;
+
+/// A level indexed by a broad phase of `cell_size` cells, with the systems of the collision
+/// step already set up.
+fn level(tiles: usize, cell_size: f64) -> (World, BroadPhaseSystem) {
+    let mut game = Game::new();
+    game.register_layer("world");
+    game.register_layer("player");
+    let mut world = game.world;
+    world.insert(BroadPhase::new(cell_size));
+    let mut broad_phase = BroadPhaseSystem::default();
+    RunNow::setup(&mut broad_phase, &mut world);
+    let width = (tiles as f64).sqrt() as usize;
+
+    for i in 0..tiles {
+        let x = (i % width) as f64 * 40.0;
+        let y = -((i / width) as f64) * 80.0;
+        world
+            .create_entity()
+            .with(TransformBuilder::new().position(Vec2::from((x, y))).build())
+            .with(
+                ColliderBuilder::new()
+                    .collider_type(ColliderType::Rect(40.0, 40.0))
+                    .build(),
+            )
+            .with(CollisionFilter::new(WORLD, WORLD))
+            .with(Collisions::default())
+            .build();
+    }
+    for i in 0..10 {
+        world
+            .create_entity()
+            .with(
+                TransformBuilder::new()
+                    .position(Vec2::from((i as f64 * 40.0, 40.0)))
+                    .build(),
+            )
+            .with(RigidBodyBuilder::new().set_mass(10.0).build())
+            .with(
+                ColliderBuilder::new()
+                    .collider_type(ColliderType::Rect(14.0, 30.0))
+                    .build(),
+            )
+            .with(Collisions::default())
+            .with(CollisionFilter::new(PLAYER, WORLD))
+            .build();
+    }
+
+    (world, broad_phase)
+}
+
+/// Runs the collision step alone. A single infinite cell puts every collider in the same
+/// bucket, which is pairing them all by brute force.
+fn collision(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collision");
+    for tiles in [100, 400, 1600].iter() {
+        for (name, cell_size) in [
+            ("broad_phase", DEFAULT_CELL_SIZE),
+            ("brute_force", f64::INFINITY),
+        ]
+        .iter()
+        {
+            let (world, mut broad_phase) = level(*tiles, *cell_size);
+            group.bench_with_input(BenchmarkId::new(*name, tiles), tiles, |b, _| {
+                b.iter(|| {
+                    broad_phase.run_now(&world);
+                    CollisionSystem.run_now(&world);
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, collision);
+criterion_main!(benches);
@@ -0,0 +1,331 @@
+use super::Collider;
+use crate::physics::{BodyType, RigidBody};
+use crate::transform::Transform;
+use math::Vec2;
+use specs::prelude::*;
+use specs::world::Index;
+use std::collections::HashMap;
+
+pub const DEFAULT_CELL_SIZE: f64 = 64.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec2<f64>,
+    pub max: Vec2<f64>,
+}
+
+impl Aabb {
+    pub fn from_center(center: Vec2<f64>, half_extents: Vec2<f64>) -> Self {
+        Aabb {
+            min: center - half_extents,
+            max: center + half_extents,
+        }
+    }
+
+    /// Smallest bounds containing both.
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: Vec2::from((self.min.x.min(other.min.x), self.min.y.min(other.min.y))),
+            max: Vec2::from((self.max.x.max(other.max.x), self.max.y.max(other.max.y))),
+        }
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+}
+
+/// Uniform grid bucketing entities by the cells their bounds cover.
+pub struct Grid {
+    cell_size: f64,
+    cells: HashMap<(i32, i32), Vec<(Entity, Aabb)>>,
+}
+
+impl Grid {
+    pub fn new(cell_size: f64) -> Self {
+        Grid {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    fn cell_range(&self, aabb: &Aabb) -> ((i32, i32), (i32, i32)) {
+        (
+            (
+                (aabb.min.x / self.cell_size).floor() as i32,
+                (aabb.min.y / self.cell_size).floor() as i32,
+            ),
+            (
+                (aabb.max.x / self.cell_size).floor() as i32,
+                (aabb.max.y / self.cell_size).floor() as i32,
+            ),
+        )
+    }
+
+    pub fn insert(&mut self, entity: Entity, aabb: Aabb) {
+        let ((x_min, y_min), (x_max, y_max)) = self.cell_range(&aabb);
+        for x in x_min..=x_max {
+            for y in y_min..=y_max {
+                self.cells.entry((x, y)).or_default().push((entity, aabb));
+            }
+        }
+    }
+
+    pub fn remove(&mut self, entity: Entity, aabb: &Aabb) {
+        let ((x_min, y_min), (x_max, y_max)) = self.cell_range(aabb);
+        for x in x_min..=x_max {
+            for y in y_min..=y_max {
+                if let Some(cell) = self.cells.get_mut(&(x, y)) {
+                    cell.retain(|(e, _)| *e != entity);
+                    if cell.is_empty() {
+                        self.cells.remove(&(x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Pushes every entity whose bounds overlap `aabb` into `out`, possibly more than once.
+    pub fn query(&self, aabb: &Aabb, out: &mut Vec<Entity>) {
+        let ((x_min, y_min), (x_max, y_max)) = self.cell_range(aabb);
+        let mut push = |cell: &Vec<(Entity, Aabb)>| {
+            out.extend(
+                cell.iter()
+                    .filter(|(_, other)| aabb.overlaps(other))
+                    .map(|(e, _)| *e),
+            );
+        };
+        // Large bounds, such as long rays, cover fewer occupied cells than empty ones.
+        let area = (x_max as i64 - x_min as i64 + 1) * (y_max as i64 - y_min as i64 + 1);
+        if area > self.cells.len() as i64 {
+            for ((x, y), cell) in &self.cells {
+                if (x_min..=x_max).contains(x) && (y_min..=y_max).contains(y) {
+                    push(cell);
+                }
+            }
+            return;
+        }
+        for x in x_min..=x_max {
+            for y in y_min..=y_max {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    push(cell);
+                }
+            }
+        }
+    }
+}
+
+/// Where a static collider was when it was indexed.
+type Placement = (Vec2<f64>, f64, Vec2<f64>);
+
+fn placement(t: &Transform) -> Placement {
+    (t.position, t.rotation, t.scale)
+}
+
+/// Spatial index of every collider, kept up to date by `BroadPhaseSystem`.
+///
+/// Static colliders, the ones without a `RigidBody` or with a static one, are indexed once and
+/// only updated when their collider changes or they are moved. Dynamic colliders are re-indexed
+/// every step.
+pub struct BroadPhase {
+    statics: Grid,
+    indexed: HashMap<Index, (Entity, Aabb, Placement)>,
+    dynamics: Grid,
+    moving: HashMap<Index, Placement>,
+    moved: BitSet,
+}
+
+impl Default for BroadPhase {
+    fn default() -> Self {
+        BroadPhase::new(DEFAULT_CELL_SIZE)
+    }
+}
+
+impl BroadPhase {
+    pub fn new(cell_size: f64) -> Self {
+        BroadPhase {
+            statics: Grid::new(cell_size),
+            indexed: HashMap::new(),
+            dynamics: Grid::new(cell_size),
+            moving: HashMap::new(),
+            moved: BitSet::new(),
+        }
+    }
+
+    fn remove_static(&mut self, id: Index) {
+        if let Some((e, aabb, _)) = self.indexed.remove(&id) {
+            self.statics.remove(e, &aabb);
+        }
+    }
+
+    fn insert_static(&mut self, e: Entity, aabb: Aabb, placement: Placement) {
+        self.statics.insert(e, aabb);
+        self.indexed.insert(e.id(), (e, aabb, placement));
+    }
+
+    /// Whether the collider of `e` was changed, moved or removed during the last step.
+    pub fn has_moved(&self, e: Entity) -> bool {
+        self.moved.contains(e.id())
+    }
+
+    /// Candidates overlapping `aabb`, sorted by entity id and without duplicates.
+    pub fn candidates(&self, aabb: &Aabb) -> Vec<Entity> {
+        let mut res = Vec::new();
+        self.statics.query(aabb, &mut res);
+        self.dynamics.query(aabb, &mut res);
+        res.sort_by_key(|e| e.id());
+        res.dedup();
+        res
+    }
+}
+
+/// Updates the `BroadPhase`. It runs at the end of the physics stage, so that continuous
+/// collision detection and the collision step both see the positions of this step.
+#[derive(Default)]
+pub struct BroadPhaseSystem {
+    colliders: Option<ReaderId<ComponentEvent>>,
+}
+impl<'a> System<'a> for BroadPhaseSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Collider>,
+        ReadStorage<'a, Transform>,
+        ReadStorage<'a, RigidBody>,
+        Write<'a, BroadPhase>,
+    );
+
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        self.colliders = Some(WriteStorage::<Collider>::fetch(world).register_reader());
+    }
+
+    fn run(
+        &mut self,
+        (entities, colliders, transforms, rigid_bodies, mut broad_phase): Self::SystemData,
+    ) {
+        let is_static = |e| {
+            rigid_bodies
+                .get(e)
+                .map(|r: &RigidBody| r.body_type == BodyType::Static)
+                .unwrap_or(true)
+        };
+
+        // Statics to re-index are the added, changed or removed colliders, the moved ones...
+        let mut dirty = BitSet::new();
+        for event in colliders.channel().read(
+            self.colliders
+                .as_mut()
+                .expect("BroadPhaseSystem is not set up"),
+        ) {
+            match event {
+                ComponentEvent::Inserted(id)
+                | ComponentEvent::Modified(id)
+                | ComponentEvent::Removed(id) => {
+                    dirty.add(*id);
+                }
+            }
+        }
+        for (id, (e, _, at)) in &broad_phase.indexed {
+            let moved = transforms
+                .get(*e)
+                .map(|t| placement(t) != *at)
+                .unwrap_or(true);
+            if moved || !entities.is_alive(*e) || !is_static(*e) {
+                dirty.add(*id);
+            }
+        }
+
+        // ... and the bodies that stopped being dynamic.
+        let mut moving = HashMap::new();
+        let mut moved = dirty.clone();
+        broad_phase.dynamics.clear();
+        for (e, c, t, _) in (&entities, &colliders, &transforms, &rigid_bodies).join() {
+            if !is_static(e) {
+                if broad_phase.moving.get(&e.id()) != Some(&placement(t)) {
+                    moved.add(e.id());
+                }
+                moving.insert(e.id(), placement(t));
+                broad_phase.dynamics.insert(e, c.aabb(t));
+            }
+        }
+        for id in broad_phase.moving.keys() {
+            if !moving.contains_key(id) {
+                dirty.add(*id);
+                moved.add(*id);
+            }
+        }
+        broad_phase.moving = moving;
+        broad_phase.moved = moved;
+
+        for id in (&dirty).join() {
+            broad_phase.remove_static(id);
+            let e = entities.entity(id);
+            if !entities.is_alive(e) || !is_static(e) {
+                continue;
+            }
+            if let (Some(c), Some(t)) = (colliders.get(e), transforms.get(e)) {
+                broad_phase.insert_static(e, c.aabb(t), placement(t));
+            }
+        }
+    }
+}
+
+#[test]
+fn statics_follow_changes() {
+    use super::{ColliderBuilder, ColliderType};
+    use crate::transform::TransformBuilder;
+    use specs::shred::RunNow;
+
+    let mut world = World::new();
+    world.register::<Collider>();
+    world.register::<Transform>();
+    world.register::<RigidBody>();
+    let mut system = BroadPhaseSystem::default();
+    RunNow::setup(&mut system, &mut world);
+    let wall = world
+        .create_entity()
+        .with(Transform::default())
+        .with(
+            ColliderBuilder::new()
+                .collider_type(ColliderType::Rect(10.0, 10.0))
+                .build(),
+        )
+        .build();
+    let around = |x: f64| Aabb::from_center(Vec2::from((x, 0.0)), Vec2::from((1.0, 1.0)));
+
+    system.run_now(&world);
+    assert_eq!(
+        world.read_resource::<BroadPhase>().candidates(&around(0.0)),
+        vec![wall]
+    );
+
+    world
+        .write_storage::<Transform>()
+        .insert(
+            wall,
+            TransformBuilder::new()
+                .position(Vec2::from((200.0, 0.0)))
+                .build(),
+        )
+        .unwrap();
+    system.run_now(&world);
+    {
+        let broad_phase = world.read_resource::<BroadPhase>();
+        assert!(broad_phase.candidates(&around(0.0)).is_empty());
+        assert_eq!(broad_phase.candidates(&around(200.0)), vec![wall]);
+    }
+
+    world.write_storage::<Collider>().remove(wall);
+    system.run_now(&world);
+    assert!(world
+        .read_resource::<BroadPhase>()
+        .candidates(&around(200.0))
+        .is_empty());
+}
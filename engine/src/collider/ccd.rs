@@ -0,0 +1,279 @@
+use super::{Aabb, BroadPhase, Collider, ColliderType, CollisionFilter, Collisions, Shape};
+use crate::physics::{BodyType, RigidBody};
+use crate::transform::Transform;
+use math::Vec2;
+use specs::prelude::*;
+
+/// How far past the time of impact a body is left, so that the collision step sees the contact.
+const SKIN: f64 = 0.01;
+
+/// Continuous collision detection. Bodies flagged `ccd` are stopped at their first impact along
+/// the path travelled during the step, instead of tunneling through thin colliders.
+///
+/// Colliders are looked up in the `BroadPhase` by the bounds swept by each `ccd` body, which
+/// grow with its speed, so the flag is meant for a handful of fast bodies such as projectiles.
+pub struct CcdSystem;
+impl<'a> System<'a> for CcdSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, BroadPhase>,
+        ReadStorage<'a, Collider>,
+        ReadStorage<'a, CollisionFilter>,
+        ReadStorage<'a, Collisions>,
+        ReadStorage<'a, RigidBody>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, broad_phase, colliders, filters, collisions, rigid_bodies, mut transforms): Self::SystemData,
+    ) {
+        let mut stops = Vec::new();
+        for (e, c1, r, t) in (&entities, &colliders, &rigid_bodies, &transforms).join() {
+            if !r.ccd || r.body_type != BodyType::Dynamic || c1.is_sensor() {
+                continue;
+            }
+            let motion = t.position - t.previous_position();
+            let distance = motion.module();
+            if distance == 0.0 {
+                continue;
+            }
+            let mut start = t.clone();
+            start.position = t.previous_position();
+            let from = c1.aabb(&start);
+            let swept = from.union(&c1.aabb(t));
+            let f1 = filters.get(e).cloned().unwrap_or_default();
+            let ignoring = |e2| {
+                collisions
+                    .get(e)
+                    .map(|c| c.is_ignoring(e2))
+                    .unwrap_or(false)
+            };
+
+            let mut first: Option<f64> = None;
+            for e2 in broad_phase.candidates(&swept) {
+                let (c2, t2) = match (colliders.get(e2), transforms.get(e2)) {
+                    (Some(c2), Some(t2)) => (c2, t2),
+                    _ => continue,
+                };
+                let f2 = filters.get(e2).cloned().unwrap_or_default();
+                // Colliders already touching at the start of the step are left to the collision
+                // step.
+                if e == e2
+                    || c2.is_sensor()
+                    || !f1.collides_with(&f2)
+                    || ignoring(e2)
+                    || c2.one_way().map(|d| motion.dot(d) >= 0.0).unwrap_or(false)
+                    || from.overlaps(&c2.aabb(t2))
+                {
+                    continue;
+                }
+                if let Some(toi) = time_of_impact(c1, &start, motion, c2, t2) {
+                    if first.map(|f| toi < f).unwrap_or(true) {
+                        first = Some(toi);
+                    }
+                }
+            }
+
+            if let Some(toi) = first {
+                stops.push((
+                    e,
+                    start.position + motion * (toi + SKIN / distance).min(1.0),
+                ));
+            }
+        }
+
+        for (e, position) in stops {
+            transforms.get_mut(e).unwrap().position = position;
+        }
+    }
+}
+
+/// Time in `[0, 1]` at which `shape`, placed at `start` and moving by `motion`, first touches
+/// `other`, 0 if they overlap from the start.
+pub(crate) fn time_of_impact(
+    shape: &ColliderType,
+    start: &Transform,
+    motion: Vec2<f64>,
+    other: &ColliderType,
+    t2: &Transform,
+) -> Option<f64> {
+    let hits = |toi: f64| {
+        let mut at = start.clone();
+        at.position += motion * toi;
+        shape.collide_with(other, &at, t2).is_some()
+    };
+    if hits(0.0) {
+        return Some(0.0);
+    }
+    let distance = motion.module();
+    if distance == 0.0 {
+        return None;
+    }
+
+    // Shapes can't touch before their bounds do.
+    let (a, b) = (shape.aabb(start), other.aabb(t2));
+    let toi = match (shape.shape(start), other.shape(t2)) {
+        (Some(Shape::Circle(p1, r1)), Some(Shape::Circle(p2, r2))) => {
+            sweep_circle(p1 - p2, r1 + r2, motion)?
+        }
+        _ if a.overlaps(&b) => 0.0,
+        _ => sweep_aabb(&a, motion, &b)?,
+    };
+
+    // Bounds may meet before the shapes do, advance by steps small enough not to skip over
+    // either shape until they touch, then bisect.
+    let step = [
+        a.max.x - a.min.x,
+        a.max.y - a.min.y,
+        b.max.x - b.min.x,
+        b.max.y - b.min.y,
+    ]
+    .iter()
+    .cloned()
+    .filter(|s| *s > 0.0)
+    .fold(distance, f64::min)
+        / 2.0
+        / distance;
+    let (mut lo, mut hi) = (toi, toi);
+    while !hits(hi) {
+        if hi >= 1.0 {
+            return None;
+        }
+        lo = hi;
+        hi = (hi + step).min(1.0);
+    }
+    for _ in 0..32 {
+        let mid = (lo + hi) / 2.0;
+        if hits(mid) {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    Some(hi)
+}
+
+/// Time in `[0, 1]` at which `a`, moving by `motion`, starts overlapping `b`. Bounds overlapping
+/// from the start are left to the collision step.
+fn sweep_aabb(a: &Aabb, motion: Vec2<f64>, b: &Aabb) -> Option<f64> {
+    let mut enter = f64::NEG_INFINITY;
+    let mut exit = f64::INFINITY;
+    for (a_min, a_max, b_min, b_max, v) in [
+        (a.min.x, a.max.x, b.min.x, b.max.x, motion.x),
+        (a.min.y, a.max.y, b.min.y, b.max.y, motion.y),
+    ]
+    .iter()
+    {
+        if *v == 0.0 {
+            if a_max <= b_min || b_max <= a_min {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (b_min - a_max) / v;
+        let t2 = (b_max - a_min) / v;
+        enter = enter.max(t1.min(t2));
+        exit = exit.min(t1.max(t2));
+    }
+
+    if enter < exit && enter > 0.0 && enter <= 1.0 {
+        Some(enter)
+    } else {
+        None
+    }
+}
+
+/// Time in `[0, 1]` at which two circles `offset` apart and moving by `motion` relative to each
+/// other start touching.
+fn sweep_circle(offset: Vec2<f64>, radius: f64, motion: Vec2<f64>) -> Option<f64> {
+    let a = motion.dot(motion);
+    let b = 2.0 * offset.dot(motion);
+    let c = offset.dot(offset) - radius * radius;
+    let disc = b * b - 4.0 * a * c;
+    if c <= 0.0 || disc < 0.0 {
+        return None;
+    }
+    let toi = (-b - disc.sqrt()) / (2.0 * a);
+    if toi > 0.0 && toi <= 1.0 {
+        Some(toi)
+    } else {
+        None
+    }
+}
+
+/// Position after a step of a body shot from `from` at `velocity`, next to `wall` at the origin.
+#[cfg(test)]
+fn shoot(
+    body: crate::physics::RigidBodyBuilder,
+    collider: super::ColliderType,
+    from: Vec2<f64>,
+    velocity: Vec2<f64>,
+    wall: super::ColliderType,
+) -> Vec2<f64> {
+    use super::ColliderBuilder;
+
+    let mut h = crate::testing::Harness::new();
+    h.add_collider(
+        Vec2::default(),
+        ColliderBuilder::new().collider_type(wall).build(),
+    );
+    let mut body = body.gravity_scale(0.0).build();
+    body.velocity = velocity;
+    let e = h.add_body(
+        from,
+        body,
+        ColliderBuilder::new().collider_type(collider).build(),
+    );
+    h.frame(&[]).of(e).unwrap().position
+}
+
+#[test]
+fn fast_body_does_not_tunnel() {
+    use super::ColliderType;
+    use crate::physics::RigidBodyBuilder;
+
+    let fall = |body, collider| {
+        shoot(
+            body,
+            collider,
+            Vec2::from((0.0, 60.0)),
+            Vec2::from((0.0, -6000.0)),
+            ColliderType::Rect(40.0, 40.0),
+        )
+        .y
+    };
+    assert!(fall(RigidBodyBuilder::new(), ColliderType::Rect(4.0, 4.0)) < -20.0);
+    let y = fall(RigidBodyBuilder::new().ccd(), ColliderType::Rect(4.0, 4.0));
+    assert!((y - 22.0).abs() < 0.1, "y = {}", y);
+    let y = fall(RigidBodyBuilder::new().ccd(), ColliderType::Circle(2.0));
+    assert!((y - 22.0).abs() < 0.1, "y = {}", y);
+}
+
+#[test]
+fn clips_corner_diagonally() {
+    use super::ColliderType;
+    use crate::physics::RigidBodyBuilder;
+
+    // Bounds meet a little before the circle reaches the top corner of the wall.
+    let p = shoot(
+        RigidBodyBuilder::new().ccd(),
+        ColliderType::Circle(5.0),
+        Vec2::from((-50.0, 150.0)),
+        Vec2::from((6000.0, -6000.0)),
+        ColliderType::Rect(4.0, 200.0),
+    );
+    assert!(p.x < 0.0, "{:?}", p);
+    let corner = Vec2::from((-2.0, 100.0));
+    assert!(((p - corner).module() - 5.0).abs() < 0.1, "{:?}", p);
+}
+
+#[test]
+fn circles_meet_at_time_of_impact() {
+    let toi = sweep_circle(Vec2::from((10.0, 0.0)), 2.0, Vec2::from((-16.0, 0.0)));
+    assert_eq!(toi, Some(0.5));
+    assert_eq!(
+        sweep_circle(Vec2::from((10.0, 5.0)), 2.0, Vec2::from((-16.0, 0.0))),
+        None
+    );
+}
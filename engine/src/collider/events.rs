@@ -0,0 +1,18 @@
+use super::Collision;
+use specs::prelude::*;
+
+/// Published by the collision system when `entity` starts touching `collision.with`. Entities
+/// both having `Collisions` each get their own event.
+#[derive(Clone, Debug)]
+pub struct CollisionStarted {
+    pub entity: Entity,
+    pub collision: Collision,
+}
+
+/// Published by the collision system when `entity` stops touching `collision.with`, `collision`
+/// being the last contact recorded between them.
+#[derive(Clone, Debug)]
+pub struct CollisionEnded {
+    pub entity: Entity,
+    pub collision: Collision,
+}
@@ -0,0 +1,84 @@
+use specs::prelude::*;
+
+/// Which layers an entity is in (`membership`) and which layers it records collisions with
+/// (`mask`). Colliders without a filter use `CollisionFilter::default()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionFilter {
+    pub membership: u32,
+    pub mask: u32,
+}
+
+impl Default for CollisionFilter {
+    fn default() -> Self {
+        CollisionFilter {
+            membership: CollisionLayers::DEFAULT,
+            mask: u32::MAX,
+        }
+    }
+}
+
+impl CollisionFilter {
+    pub fn new(membership: u32, mask: u32) -> Self {
+        CollisionFilter { membership, mask }
+    }
+
+    pub fn collides_with(&self, other: &CollisionFilter) -> bool {
+        self.mask & other.membership != 0
+    }
+}
+
+impl Component for CollisionFilter {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Named collision layers, each one owning a bit of `CollisionFilter::membership`.
+pub struct CollisionLayers {
+    names: Vec<String>,
+}
+
+impl Default for CollisionLayers {
+    fn default() -> Self {
+        CollisionLayers {
+            names: vec![String::from("default")],
+        }
+    }
+}
+
+impl CollisionLayers {
+    pub const DEFAULT: u32 = 1;
+
+    /// Returns the bit of `name`, registering it if needed.
+    pub fn register(&mut self, name: &str) -> u32 {
+        if let Some(bit) = self.get(name) {
+            return bit;
+        }
+        assert!(self.names.len() < 32, "no more than 32 collision layers");
+        self.names.push(name.into());
+        1 << (self.names.len() - 1)
+    }
+
+    pub fn get(&self, name: &str) -> Option<u32> {
+        self.names.iter().position(|n| n == name).map(|i| 1 << i)
+    }
+
+    /// Union of the bits of every layer in `names`, ignoring unknown ones.
+    pub fn mask(&self, names: &[&str]) -> u32 {
+        names
+            .iter()
+            .filter_map(|n| self.get(n))
+            .fold(0, |a, b| a | b)
+    }
+}
+
+#[test]
+fn registers_layers_once() {
+    let mut layers = CollisionLayers::default();
+    let player = layers.register("player");
+    let enemy = layers.register("enemy");
+
+    assert_eq!(layers.get("default"), Some(CollisionLayers::DEFAULT));
+    assert_eq!(player, 2);
+    assert_eq!(enemy, 4);
+    assert_eq!(layers.register("player"), player);
+    assert_eq!(layers.mask(&["player", "enemy", "pickup"]), 6);
+}
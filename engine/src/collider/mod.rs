@@ -0,0 +1,929 @@
+mod broad_phase;
+mod ccd;
+mod events;
+mod filter;
+mod platform;
+mod response;
+mod shape;
+
+pub use broad_phase::{Aabb, BroadPhase, BroadPhaseSystem, DEFAULT_CELL_SIZE};
+pub(crate) use ccd::time_of_impact;
+pub use ccd::CcdSystem;
+pub use events::{CollisionEnded, CollisionStarted};
+pub use filter::{CollisionFilter, CollisionLayers};
+pub use platform::CarrySystem;
+pub use response::RepultionSystem;
+pub use shape::{Contact, Shape};
+
+use crate::physics::{PhysicsConfig, RigidBody};
+use crate::transform::Transform;
+// use log::*;
+use math::Vec2;
+use specs::prelude::*;
+use specs::shrev::EventChannel;
+use std::collections::HashSet;
+use std::ops::{Deref, DerefMut};
+use std::time::Duration;
+
+pub struct CollisionSystem;
+impl<'a> System<'a> for CollisionSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, PhysicsConfig>,
+        Read<'a, BroadPhase>,
+        WriteStorage<'a, Collisions>,
+        ReadStorage<'a, Collider>,
+        ReadStorage<'a, Transform>,
+        ReadStorage<'a, RigidBody>,
+        ReadStorage<'a, CollisionFilter>,
+        Write<'a, EventChannel<CollisionStarted>>,
+        Write<'a, EventChannel<CollisionEnded>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            config,
+            broad_phase,
+            mut collisions,
+            colliders,
+            transforms,
+            rigid_bodies,
+            filters,
+            mut started,
+            mut ended,
+        ): Self::SystemData,
+    ) {
+        let mut ignored = HashSet::new();
+        for (e, c) in (&entities, &mut collisions).join() {
+            for (e2, left) in &mut c.ignored {
+                ignored.insert((e, *e2));
+                ignored.insert((*e2, e));
+                *left = left.checked_sub(config.time_step).unwrap_or_default();
+            }
+            c.ignored.retain(|(_, left)| *left > Duration::default());
+        }
+
+        for (e, c, c1, t) in (&entities, &mut collisions, &colliders, &transforms).join() {
+            // Sleeping bodies keep the contacts they fell asleep with, unless one of them moved
+            // or went away, `SleepSystem` then wakes them up.
+            if rigid_bodies
+                .get(e)
+                .map(|r| r.is_sleeping())
+                .unwrap_or(false)
+                && c.ignored.is_empty()
+                && c.iter()
+                    .all(|hit| entities.is_alive(hit.with) && !broad_phase.has_moved(hit.with))
+            {
+                continue;
+            }
+            let f1 = filters.get(e).cloned().unwrap_or_default();
+            let mut current = Vec::new();
+            for e2 in broad_phase.candidates(&c1.aabb(t)) {
+                let f2 = filters.get(e2).cloned().unwrap_or_default();
+                if e != e2 && f1.collides_with(&f2) && !ignored.contains(&(e, e2)) {
+                    let c2 = colliders.get(e2).unwrap();
+                    let t2 = transforms.get(e2).unwrap();
+                    let contact = c1.collide_with(c2, t, t2).and_then(|contact| {
+                        match (c1.one_way, c2.one_way) {
+                            (_, Some(dir)) => platform::one_way_contact(dir, c1, t, c2, t2),
+                            (Some(dir), _) => {
+                                platform::one_way_contact(dir, c2, t2, c1, t).map(|c| Contact {
+                                    normal: -c.normal,
+                                    depth: c.depth,
+                                })
+                            }
+                            _ => Some(contact),
+                        }
+                    });
+                    if let Some(contact) = contact {
+                        current.push(Collision {
+                            with: e2,
+                            at: contact.translation(),
+                            normal: contact.normal,
+                            depth: contact.depth,
+                            points: c1.contact_points(c2, t, t2, &contact),
+                            side: Side::from_normal(contact.normal),
+                            state: ContactState::Began,
+                            sensor: c1.sensor || c2.sensor,
+                        });
+                    }
+                }
+            }
+            c.update(current);
+            for collision in c.entered() {
+                started.single_write(CollisionStarted {
+                    entity: e,
+                    collision: collision.clone(),
+                });
+            }
+            for collision in c.exited() {
+                ended.single_write(CollisionEnded {
+                    entity: e,
+                    collision: collision.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Collider shapes, in local space. They are rotated by `Transform::rotation` and scaled by
+/// `Transform::scale` before being tested.
+#[derive(Clone, Debug)]
+pub enum ColliderType {
+    Circle(f64),
+    Rect(f64, f64),
+    /// Convex polygon, points are relative to the entity position.
+    Polygon(Vec<Vec2<f64>>),
+    /// Vertical pill of the given width and total height.
+    Capsule(f64, f64),
+    Segment(Vec2<f64>, Vec2<f64>),
+    /// Polyline, for level geometry that would otherwise need many boxes.
+    Chain(Vec<Vec2<f64>>),
+    None,
+}
+impl ColliderType {
+    /// World space shape of the collider placed at `t`.
+    pub fn shape(&self, t: &Transform) -> Option<Shape> {
+        let scale = Vec2::from((t.scale.x.abs(), t.scale.y.abs()));
+        let place = |p: Vec2<f64>| p.rotate(t.rotation) + t.position;
+        match self {
+            ColliderType::Circle(r) => Some(Shape::Circle(t.position, r * scale.x.max(scale.y))),
+            ColliderType::Rect(w, h) => {
+                let w = w * scale.x / 2.0;
+                let h = h * scale.y / 2.0;
+                Some(Shape::Polygon(
+                    [(-w, -h), (w, -h), (w, h), (-w, h)]
+                        .iter()
+                        .map(|p| place(Vec2::from(*p)))
+                        .collect(),
+                ))
+            }
+            ColliderType::Polygon(points) if !points.is_empty() => Some(Shape::Polygon(
+                points.iter().map(|p| place(p.scale(t.scale))).collect(),
+            )),
+            ColliderType::Capsule(w, h) => {
+                let r = w * scale.x / 2.0;
+                let half = (h * scale.y / 2.0 - r).max(0.0);
+                Some(Shape::Capsule(
+                    place(Vec2::from((0.0, -half))),
+                    place(Vec2::from((0.0, half))),
+                    r,
+                ))
+            }
+            ColliderType::Segment(a, b) => Some(Shape::Segment(
+                place(a.scale(t.scale)),
+                place(b.scale(t.scale)),
+            )),
+            ColliderType::Chain(points) if points.len() > 1 => Some(Shape::Chain(
+                points.iter().map(|p| place(p.scale(t.scale))).collect(),
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn aabb(&self, t: &Transform) -> Aabb {
+        match self.shape(t) {
+            Some(s) => s.aabb(),
+            None => Aabb::from_center(t.position, Vec2::default()),
+        }
+    }
+
+    /// Moment of inertia of the collider around the entity position, for a uniform `mass`, to be
+    /// given to `RigidBodyBuilder::inertia`. Capsules are treated as boxes.
+    pub fn inertia(&self, mass: f64) -> f64 {
+        // Thin rod from `a` to `b`.
+        let rod =
+            |mass: f64, a: Vec2<f64>, b: Vec2<f64>| mass * (a.dot(a) + a.dot(b) + b.dot(b)) / 3.0;
+        match self {
+            ColliderType::Circle(r) => mass * r * r / 2.0,
+            ColliderType::Rect(w, h) | ColliderType::Capsule(w, h) => mass * (w * w + h * h) / 12.0,
+            ColliderType::Polygon(points) => {
+                let (mut area, mut sum) = (0.0, 0.0);
+                for (i, a) in points.iter().enumerate() {
+                    let b = points[(i + 1) % points.len()];
+                    let cross = a.cross(b);
+                    area += cross;
+                    sum += cross * (a.dot(*a) + a.dot(b) + b.dot(b));
+                }
+                if area == 0.0 {
+                    0.0
+                } else {
+                    mass * sum / (6.0 * area)
+                }
+            }
+            ColliderType::Segment(a, b) => rod(mass, *a, *b),
+            ColliderType::Chain(points) => {
+                let length: f64 = points.windows(2).map(|w| (w[1] - w[0]).module()).sum();
+                if length == 0.0 {
+                    return 0.0;
+                }
+                points
+                    .windows(2)
+                    .map(|w| rod(mass * (w[1] - w[0]).module() / length, w[0], w[1]))
+                    .sum()
+            }
+            ColliderType::None => 0.0,
+        }
+    }
+
+    fn is_axis_aligned(&self, t: &Transform) -> bool {
+        match self {
+            ColliderType::Circle(_) => true,
+            ColliderType::Rect(_, _) => t.rotation == 0.0,
+            _ => false,
+        }
+    }
+
+    fn scaled(&self, scale: Vec2<f64>) -> ColliderType {
+        match self {
+            ColliderType::Circle(r) => ColliderType::Circle(r * scale.x.abs().max(scale.y.abs())),
+            ColliderType::Rect(w, h) => ColliderType::Rect(w * scale.x.abs(), h * scale.y.abs()),
+            _ => ColliderType::None,
+        }
+    }
+
+    /// Contact pushing the first collider out of the second one, if they overlap.
+    pub fn collide_with(
+        &self,
+        other: &ColliderType,
+        t1: &Transform,
+        t2: &Transform,
+    ) -> Option<Contact> {
+        if self.is_axis_aligned(t1) && other.is_axis_aligned(t2) {
+            self.scaled(t1.scale)
+                .collide_aligned(&other.scaled(t2.scale), t1.position, t2.position)
+                .map(Contact::from_translation)
+        } else {
+            shape::collide(&self.shape(t1)?, &other.shape(t2)?)
+        }
+    }
+
+    /// World space points where the two colliders touch, given their `contact`.
+    pub fn contact_points(
+        &self,
+        other: &ColliderType,
+        t1: &Transform,
+        t2: &Transform,
+        contact: &Contact,
+    ) -> Vec<Vec2<f64>> {
+        match (self.shape(t1), other.shape(t2)) {
+            (Some(a), Some(b)) => shape::contact_points(&a, &b, contact),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Displacement to apply to the first shape to push it out of the second one.
+    fn collide_aligned(
+        &self,
+        other: &ColliderType,
+        p1: Vec2<f64>,
+        p2: Vec2<f64>,
+    ) -> Option<Vec2<f64>> {
+        match (self, other) {
+            (ColliderType::Circle(r1), ColliderType::Circle(r2)) => {
+                let line = p2 - p1;
+                let dist = line.amplitude_squared();
+                let rad = (r1 + r2) * (r1 + r2);
+                if rad > dist {
+                    let depl = rad.sqrt() - dist.sqrt();
+                    // Same centre, fall back to the y axis as when a rect contains a circle.
+                    let dir = if dist > 0.0 {
+                        line.normalize()
+                    } else {
+                        Vec2::from((0.0, 1.0))
+                    };
+                    Some(-dir * depl)
+                } else {
+                    None
+                }
+            }
+            (ColliderType::Rect(w1, h1), ColliderType::Rect(w2, h2)) => {
+                // Overlap on each axis, the boxes are pushed apart along the shallowest one.
+                let d = p1 - p2;
+                let x = (w1 + w2) / 2.0 - d.x.abs();
+                let y = (h1 + h2) / 2.0 - d.y.abs();
+                if x <= 0.0 || y <= 0.0 {
+                    None
+                } else if x < y {
+                    Some(Vec2::from((if d.x < 0.0 { -x } else { x }, 0.0)))
+                } else {
+                    Some(Vec2::from((0.0, if d.y < 0.0 { -y } else { y })))
+                }
+            }
+            (ColliderType::Rect(w, h), ColliderType::Circle(r)) => {
+                let half = Vec2::from((*w / 2.0, *h / 2.0));
+                let center = p2 - p1;
+                let closest = Vec2::from((
+                    center.x.max(-half.x).min(half.x),
+                    center.y.max(-half.y).min(half.y),
+                ));
+
+                if closest != center {
+                    // Circle center outside of the rectangle, push along the closest point.
+                    let dir = closest - center;
+                    let dist = dir.module();
+                    if dist < *r {
+                        Some(dir / dist * (r - dist))
+                    } else {
+                        None
+                    }
+                } else {
+                    // Circle center inside, push out through the nearest edge.
+                    let depth_x = half.x - center.x.abs();
+                    let depth_y = half.y - center.y.abs();
+                    if depth_x < depth_y {
+                        Some(Vec2::from((-(depth_x + r) * center.x.signum(), 0.0)))
+                    } else {
+                        Some(Vec2::from((0.0, -(depth_y + r) * center.y.signum())))
+                    }
+                }
+            }
+            (ColliderType::Circle(r), ColliderType::Rect(w, h)) => ColliderType::Rect(*w, *h)
+                .collide_aligned(&ColliderType::Circle(*r), p2, p1)
+                .map(|v| -v),
+            (_, _) => None,
+        }
+    }
+}
+
+pub struct ColliderBuilder {
+    col_type: Option<ColliderType>,
+    sensor: bool,
+    one_way: Option<Vec2<f64>>,
+    restitution: f64,
+    friction: f64,
+}
+impl ColliderBuilder {
+    pub fn new() -> Self {
+        ColliderBuilder {
+            col_type: None,
+            sensor: false,
+            one_way: None,
+            restitution: 0.0,
+            friction: 0.0,
+        }
+    }
+
+    pub fn collider_type(mut self, c: ColliderType) -> Self {
+        self.col_type = Some(c);
+        self
+    }
+
+    /// Overlaps with a sensor are recorded in `Collisions` but never pushed apart.
+    pub fn sensor(mut self) -> Self {
+        self.sensor = true;
+        self
+    }
+
+    /// Only blocks colliders coming from `direction`, in world space, letting them through from
+    /// any other side. `(0, 1)` makes a ledge that can be jumped onto from below.
+    pub fn one_way(mut self, direction: Vec2<f64>) -> Self {
+        self.one_way = Some(direction.normalize());
+        self
+    }
+
+    /// Share of the approach speed given back on impact, 0 stops dead and 1 bounces forever.
+    /// The bounciest of the two colliders wins.
+    pub fn restitution(mut self, restitution: f64) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    /// Coulomb friction coefficient, the roughest of the two colliders wins.
+    pub fn friction(mut self, friction: f64) -> Self {
+        self.friction = friction;
+        self
+    }
+
+    pub fn build(self) -> Collider {
+        let ColliderBuilder {
+            col_type,
+            sensor,
+            one_way,
+            restitution,
+            friction,
+        } = self;
+
+        Collider {
+            col_type: match col_type {
+                Some(v) => v,
+                None => ColliderType::None,
+            },
+            sensor,
+            one_way,
+            restitution,
+            friction,
+        }
+    }
+}
+
+/// Side of an entity touched by a collision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+impl Side {
+    /// Side hit by a contact pushing the entity along `normal`.
+    pub fn from_normal(normal: Vec2<f64>) -> Self {
+        if normal.y.abs() >= normal.x.abs() {
+            if normal.y > 0.0 {
+                Side::Bottom
+            } else {
+                Side::Top
+            }
+        } else if normal.x > 0.0 {
+            Side::Left
+        } else {
+            Side::Right
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContactState {
+    Began,
+    Persisted,
+    Ended,
+}
+
+#[derive(Clone, Debug)]
+pub struct Collision {
+    pub with: specs::world::Entity,
+    /// Displacement pushing this entity out of `with`, `normal * depth`.
+    pub at: Vec2<f64>,
+    pub normal: Vec2<f64>,
+    pub depth: f64,
+    pub points: Vec<Vec2<f64>>,
+    pub side: Side,
+    pub state: ContactState,
+    /// One of the two colliders is a sensor, the collision is not resolved.
+    pub sensor: bool,
+}
+
+pub struct Collider {
+    col_type: ColliderType,
+    sensor: bool,
+    one_way: Option<Vec2<f64>>,
+    restitution: f64,
+    friction: f64,
+}
+impl Collider {
+    pub fn is_sensor(&self) -> bool {
+        self.sensor
+    }
+
+    /// Solid side of a one-way collider.
+    pub fn one_way(&self) -> Option<Vec2<f64>> {
+        self.one_way
+    }
+
+    pub fn restitution(&self) -> f64 {
+        self.restitution
+    }
+
+    pub fn friction(&self) -> f64 {
+        self.friction
+    }
+}
+impl Component for Collider {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+impl Deref for Collider {
+    type Target = ColliderType;
+
+    fn deref(&self) -> &Self::Target {
+        &self.col_type
+    }
+}
+impl DerefMut for Collider {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.col_type
+    }
+}
+
+#[derive(Default)]
+pub struct Collisions {
+    current: Vec<Collision>,
+    ended: Vec<Collision>,
+    ignored: Vec<(Entity, Duration)>,
+}
+impl Collisions {
+    pub fn has_hit(&self, side: Side) -> bool {
+        self.current.iter().any(|c| c.side == side)
+    }
+
+    pub fn has_hit_bottom(&self) -> bool {
+        self.has_hit(Side::Bottom)
+    }
+
+    pub fn has_hit_top(&self) -> bool {
+        self.has_hit(Side::Top)
+    }
+
+    pub fn has_hit_left(&self) -> bool {
+        self.has_hit(Side::Left)
+    }
+
+    pub fn has_hit_right(&self) -> bool {
+        self.has_hit(Side::Right)
+    }
+
+    pub fn with(&self, entity: Entity) -> Option<&Collision> {
+        self.current.iter().find(|c| c.with == entity)
+    }
+
+    /// Entities this one is standing on, sensors excluded.
+    pub fn standing_on(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.current
+            .iter()
+            .filter(|c| c.side == Side::Bottom && !c.sensor)
+            .map(|c| c.with)
+    }
+
+    /// Whether this entity is standing on `entity`, never a sensor.
+    pub fn on_ground_with(&self, entity: Entity) -> bool {
+        self.with(entity)
+            .map(|c| c.side == Side::Bottom && !c.sensor)
+            .unwrap_or(false)
+    }
+
+    /// Stops recording and resolving collisions with `entity` for `duration`, to drop through
+    /// a one-way platform for instance.
+    pub fn ignore(&mut self, entity: Entity, duration: Duration) {
+        self.ignored.retain(|(e, _)| *e != entity);
+        self.ignored.push((entity, duration));
+    }
+
+    pub fn is_ignoring(&self, entity: Entity) -> bool {
+        self.ignored.iter().any(|(e, _)| *e == entity)
+    }
+
+    pub fn entered(&self) -> impl Iterator<Item = &Collision> {
+        self.current
+            .iter()
+            .filter(|c| c.state == ContactState::Began)
+    }
+
+    pub fn stayed(&self) -> impl Iterator<Item = &Collision> {
+        self.current
+            .iter()
+            .filter(|c| c.state == ContactState::Persisted)
+    }
+
+    /// Contacts that stopped during the last step.
+    pub fn exited(&self) -> impl Iterator<Item = &Collision> {
+        self.ended.iter()
+    }
+
+    /// Replaces the current contacts, tagging them against the previous ones.
+    fn update(&mut self, mut current: Vec<Collision>) {
+        for c in &mut current {
+            c.state = if self.with(c.with).is_some() {
+                ContactState::Persisted
+            } else {
+                ContactState::Began
+            };
+        }
+        let previous = std::mem::replace(&mut self.current, current);
+        self.ended = previous
+            .into_iter()
+            .filter(|c| self.with(c.with).is_none())
+            .map(|mut c| {
+                c.state = ContactState::Ended;
+                c
+            })
+            .collect();
+    }
+}
+impl Component for Collisions {
+    type Storage = DenseVecStorage<Self>;
+}
+impl Deref for Collisions {
+    type Target = Vec<Collision>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.current
+    }
+}
+impl DerefMut for Collisions {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.current
+    }
+}
+
+#[test]
+fn rotated_box_rests_on_its_corner() {
+    use crate::transform::TransformBuilder;
+
+    let ground = TransformBuilder::new().build();
+    let tilted = TransformBuilder::new()
+        .position(Vec2::from((0.0, 20.0)))
+        .rotation(std::f64::consts::FRAC_PI_4)
+        .build();
+
+    // Half diagonal of a 20x20 box is ~14.14, the ground top is at 10.
+    let contact = ColliderType::Rect(20.0, 20.0)
+        .collide_with(&ColliderType::Rect(100.0, 20.0), &tilted, &ground)
+        .unwrap();
+    assert!((contact.normal.y - 1.0).abs() < 1e-9);
+    assert!((contact.depth - (200.0f64.sqrt() - 10.0)).abs() < 1e-9);
+
+    let aligned = TransformBuilder::new()
+        .position(Vec2::from((0.0, 20.0)))
+        .build();
+    assert!(ColliderType::Rect(20.0, 20.0)
+        .collide_with(&ColliderType::Rect(100.0, 20.0), &aligned, &ground)
+        .is_none());
+}
+
+#[test]
+fn polygon_follows_transform_scale() {
+    use crate::transform::TransformBuilder;
+
+    let triangle = ColliderType::Polygon(vec![
+        Vec2::from((-5.0, 0.0)),
+        Vec2::from((5.0, 0.0)),
+        Vec2::from((0.0, 5.0)),
+    ]);
+    let circle = TransformBuilder::new()
+        .position(Vec2::from((0.0, 12.0)))
+        .build();
+
+    let small = TransformBuilder::new().build();
+    assert!(triangle
+        .collide_with(&ColliderType::Circle(5.0), &small, &circle)
+        .is_none());
+
+    let big = TransformBuilder::new()
+        .scale(Vec2::from((2.0, 2.0)))
+        .build();
+    let contact = triangle
+        .collide_with(&ColliderType::Circle(5.0), &big, &circle)
+        .unwrap();
+    assert!(contact.normal.y < 0.0);
+}
+
+#[test]
+fn every_pair_collides() {
+    use crate::transform::TransformBuilder;
+
+    let shapes = vec![
+        ColliderType::Circle(10.0),
+        ColliderType::Rect(20.0, 20.0),
+        ColliderType::Polygon(vec![
+            Vec2::from((-10.0, -10.0)),
+            Vec2::from((10.0, -10.0)),
+            Vec2::from((0.0, 10.0)),
+        ]),
+        ColliderType::Capsule(10.0, 30.0),
+        ColliderType::Segment(Vec2::from((-10.0, -10.0)), Vec2::from((10.0, 10.0))),
+        ColliderType::Chain(vec![
+            Vec2::from((-10.0, 10.0)),
+            Vec2::from((0.0, -10.0)),
+            Vec2::from((10.0, 10.0)),
+        ]),
+        ColliderType::None,
+    ];
+    let origin = TransformBuilder::new().build();
+    let near = TransformBuilder::new()
+        .position(Vec2::from((1.0, 2.0)))
+        .build();
+    // Rotated so that open shapes cross instead of lying parallel to their copy.
+    let near_rotated = TransformBuilder::new()
+        .position(Vec2::from((1.0, 2.0)))
+        .rotation(std::f64::consts::FRAC_PI_2)
+        .build();
+    let far = TransformBuilder::new()
+        .position(Vec2::from((100.0, 40.0)))
+        .build();
+
+    let open = |c: &ColliderType| matches!(c, ColliderType::Segment(_, _) | ColliderType::Chain(_));
+    for a in &shapes {
+        for b in &shapes {
+            if let (ColliderType::None, _) | (_, ColliderType::None) = (a, b) {
+                assert!(a.collide_with(b, &near, &origin).is_none());
+                continue;
+            }
+
+            assert!(
+                a.collide_with(b, &far, &origin).is_none(),
+                "{:?} {:?}",
+                a,
+                b
+            );
+            let mut placements = vec![&near_rotated];
+            if !open(a) || !open(b) {
+                placements.push(&near);
+            }
+            for t in placements {
+                let contact = a.collide_with(b, t, &origin);
+                assert!(contact.is_some(), "{:?} {:?}", a, b);
+                let contact = contact.unwrap();
+                assert!((contact.normal.module() - 1.0).abs() < 1e-9);
+                assert!(contact.depth > 0.0);
+            }
+        }
+    }
+}
+
+#[test]
+fn rect_circle_contacts() {
+    use crate::transform::TransformBuilder;
+
+    let rect = ColliderType::Rect(20.0, 20.0);
+    let circle = ColliderType::Circle(5.0);
+    let origin = TransformBuilder::new().build();
+    let cases = [
+        // Edges.
+        ((0.0, 13.0), Some((0.0, -2.0))),
+        ((13.0, 0.0), Some((-2.0, 0.0))),
+        ((0.0, -13.0), Some((0.0, 2.0))),
+        // Center inside.
+        ((0.0, 8.0), Some((0.0, -7.0))),
+        ((-9.0, 3.0), Some((6.0, 0.0))),
+        ((0.0, 0.0), Some((0.0, -15.0))),
+        // Corner.
+        ((13.0, 13.0), Some((-0.535, -0.535))),
+        // Apart.
+        ((16.0, 0.0), None),
+        ((14.0, 14.0), None),
+    ];
+
+    for (at, expected) in cases.iter() {
+        let t = TransformBuilder::new().position(Vec2::from(*at)).build();
+        let found = rect.collide_with(&circle, &origin, &t);
+        let reversed = circle.collide_with(&rect, &t, &origin);
+        match expected {
+            Some(v) => {
+                let v = Vec2::from(*v);
+                let found = found.unwrap().translation();
+                assert!((found - v).module() < 1e-3, "{:?}: {:?}", at, found);
+                let reversed = reversed.unwrap().translation();
+                assert!((reversed + v).module() < 1e-3, "{:?}: {:?}", at, reversed);
+            }
+            None => {
+                assert!(found.is_none(), "{:?}", at);
+                assert!(reversed.is_none(), "{:?}", at);
+            }
+        }
+    }
+}
+
+#[test]
+fn coincident_circles_separate() {
+    let circle = ColliderType::Circle(5.0);
+    let origin = Transform::default();
+
+    let contact = circle.collide_with(&circle, &origin, &origin).unwrap();
+    assert_eq!(contact.normal, Vec2::from((0.0, -1.0)));
+    assert_eq!(contact.depth, 10.0);
+}
+
+#[test]
+fn capsule_glides_over_box_seam() {
+    use crate::transform::TransformBuilder;
+
+    // Bottom of the capsule sits 1 unit into the floor, right above the seam at x = 20.
+    let capsule = TransformBuilder::new()
+        .position(Vec2::from((19.0, 34.0)))
+        .build();
+    let right_tile = TransformBuilder::new()
+        .position(Vec2::from((40.0, 0.0)))
+        .build();
+
+    let contact = ColliderType::Capsule(14.0, 30.0)
+        .collide_with(&ColliderType::Rect(40.0, 40.0), &capsule, &right_tile)
+        .unwrap();
+    assert!(contact.normal.y > 0.9, "{:?}", contact);
+}
+
+#[test]
+fn box_on_floor_touches_along_its_bottom_edge() {
+    use crate::transform::TransformBuilder;
+
+    let floor = TransformBuilder::new().build();
+    let t = TransformBuilder::new()
+        .position(Vec2::from((10.0, 29.0)))
+        .build();
+    let (rect, ground) = (
+        ColliderType::Rect(20.0, 20.0),
+        ColliderType::Rect(40.0, 40.0),
+    );
+
+    let contact = rect.collide_with(&ground, &t, &floor).unwrap();
+    assert_eq!(Side::from_normal(contact.normal), Side::Bottom);
+    let mut points = rect.contact_points(&ground, &t, &floor, &contact);
+    points.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap());
+    assert_eq!(points.len(), 2);
+    assert!(
+        (points[0] - Vec2::from((0.0, 20.0))).module() < 1e-6,
+        "{:?}",
+        points
+    );
+    assert!(
+        (points[1] - Vec2::from((20.0, 20.0))).module() < 1e-6,
+        "{:?}",
+        points
+    );
+}
+
+#[test]
+fn collisions_track_enter_stay_exit() {
+    let mut world = World::new();
+    let (a, b) = (world.create_entity().build(), world.create_entity().build());
+    let hit = |with| Collision {
+        with,
+        at: Vec2::from((0.0, 1.0)),
+        normal: Vec2::from((0.0, 1.0)),
+        depth: 1.0,
+        points: Vec::new(),
+        side: Side::Bottom,
+        state: ContactState::Began,
+        sensor: false,
+    };
+
+    let mut c = Collisions::default();
+    c.update(vec![hit(a)]);
+    assert_eq!(c.entered().count(), 1);
+    assert!(c.on_ground_with(a));
+
+    c.update(vec![hit(a), hit(b)]);
+    assert_eq!(c.with(a).unwrap().state, ContactState::Persisted);
+    assert_eq!(c.with(b).unwrap().state, ContactState::Began);
+
+    c.update(vec![hit(b)]);
+    assert_eq!(c.stayed().count(), 1);
+    assert_eq!(c.exited().map(|c| c.with).collect::<Vec<_>>(), vec![a]);
+    assert!(!c.on_ground_with(a));
+
+    c.update(Vec::new());
+    assert!(!c.has_hit_bottom());
+    assert_eq!(c.exited().next().unwrap().state, ContactState::Ended);
+
+    c.update(vec![Collision {
+        sensor: true,
+        ..hit(a)
+    }]);
+    assert!(!c.on_ground_with(a));
+}
+
+#[test]
+fn raycast_hits_every_shape() {
+    use crate::transform::TransformBuilder;
+
+    let t = TransformBuilder::new().build();
+    let rotated = TransformBuilder::new().rotation(0.3).build();
+    let down = Vec2::from((0.0, -1.0));
+    let shapes = [
+        ColliderType::Circle(10.0),
+        ColliderType::Rect(20.0, 20.0),
+        ColliderType::Polygon(vec![
+            Vec2::from((-10.0, -10.0)),
+            Vec2::from((10.0, -10.0)),
+            Vec2::from((0.0, 10.0)),
+        ]),
+        ColliderType::Capsule(20.0, 40.0),
+        ColliderType::Segment(Vec2::from((-10.0, 10.0)), Vec2::from((10.0, 10.0))),
+        ColliderType::Chain(vec![
+            Vec2::from((-10.0, 0.0)),
+            Vec2::from((0.0, 10.0)),
+            Vec2::from((10.0, 0.0)),
+        ]),
+    ];
+
+    for shape in shapes.iter() {
+        let (distance, normal) = shape
+            .shape(&t)
+            .unwrap()
+            .raycast(Vec2::from((0.0, 50.0)), down)
+            .unwrap();
+        let top = if let ColliderType::Capsule(_, _) = shape {
+            20.0
+        } else {
+            10.0
+        };
+        assert!(
+            (distance - (50.0 - top)).abs() < 1e-9,
+            "{:?}: {}",
+            shape,
+            distance
+        );
+        assert!(normal.y > 0.0, "{:?}: {:?}", shape, normal);
+
+        assert!(shape
+            .shape(&rotated)
+            .unwrap()
+            .raycast(Vec2::from((0.0, 50.0)), down)
+            .is_some());
+        assert!(shape
+            .shape(&t)
+            .unwrap()
+            .raycast(Vec2::from((30.0, 50.0)), down)
+            .is_none());
+    }
+}
@@ -0,0 +1,79 @@
+use super::{ColliderType, Collisions, Contact, Side};
+use crate::physics::{BodyType, RigidBody};
+use crate::transform::Transform;
+use math::Vec2;
+use specs::prelude::*;
+
+/// Moves dynamic bodies along with the kinematic bodies they stood on at the end of the previous
+/// step, so riders are not left behind by moving platforms. Runs right after the bodies are
+/// integrated, before collisions are detected.
+///
+/// Only the displacement along the platform surface is carried, collisions already keep riders
+/// on top of platforms moving up or down.
+pub struct CarrySystem;
+impl<'a> System<'a> for CarrySystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Collisions>,
+        ReadStorage<'a, RigidBody>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(&mut self, (entities, collisions, rigid_bodies, mut transforms): Self::SystemData) {
+        let is_kinematic = |e| {
+            rigid_bodies
+                .get(e)
+                .map(|r: &RigidBody| r.body_type == BodyType::Kinematic)
+                .unwrap_or(false)
+        };
+        let carried: Vec<(Entity, Vec2<f64>)> = (&entities, &collisions, &rigid_bodies)
+            .join()
+            .filter(|(_, _, r)| r.body_type == BodyType::Dynamic)
+            .filter_map(|(e, c, _)| {
+                c.iter()
+                    .filter(|c| c.side == Side::Bottom && !c.sensor && is_kinematic(c.with))
+                    .filter_map(|c| {
+                        let t = transforms.get(c.with)?;
+                        let d = t.position - t.previous_position();
+                        Some(d - c.normal * d.dot(c.normal))
+                    })
+                    .find(|d| *d != Vec2::default())
+                    .map(|d| (e, d))
+            })
+            .collect();
+
+        for (e, d) in carried {
+            if let Some(t) = transforms.get_mut(e) {
+                t.position += d;
+            }
+        }
+    }
+}
+
+/// Penetration into a one-way platform tolerated beyond the distance travelled toward it.
+const ONE_WAY_SLOP: f64 = 0.01;
+
+/// Contact pushing `mover` out of a one-way `platform` along its solid `direction`, only if
+/// `mover` reached it from the solid side during the last step.
+pub(crate) fn one_way_contact(
+    direction: Vec2<f64>,
+    mover: &ColliderType,
+    tm: &Transform,
+    platform: &ColliderType,
+    tp: &Transform,
+) -> Option<Contact> {
+    let (_, platform_max) = platform.shape(tp)?.project(direction);
+    let (mover_min, _) = mover.shape(tm)?.project(direction);
+    let depth = platform_max - mover_min;
+    let moved = ((tm.previous_position() - tm.position) - (tp.previous_position() - tp.position))
+        .dot(direction);
+
+    if depth > 0.0 && depth <= moved.max(0.0) + ONE_WAY_SLOP {
+        Some(Contact {
+            normal: direction,
+            depth,
+        })
+    } else {
+        None
+    }
+}
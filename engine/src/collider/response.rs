@@ -0,0 +1,454 @@
+use super::{Collider, Collisions, Side};
+use crate::physics::{BodyType, PhysicsConfig, RigidBody};
+use crate::transform::Transform;
+use math::Vec2;
+use specs::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Pushes overlapping bodies out of each other and exchanges impulses between them, both
+/// weighted by their inverse mass. Colliders without a `RigidBody` never move.
+pub struct RepultionSystem;
+impl<'a> System<'a> for RepultionSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, PhysicsConfig>,
+        ReadStorage<'a, Collisions>,
+        ReadStorage<'a, Collider>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, RigidBody>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, config, collisions, colliders, mut transforms, mut rigid_bodies): Self::SystemData,
+    ) {
+        // Both entities of a pair may record the contact, it is only resolved once.
+        let mut solved = HashSet::new();
+        let mut contacts = Vec::new();
+        for (a, c) in (&entities, &collisions).join() {
+            // Contacts of sleeping bodies were found before they fell asleep.
+            if rigid_bodies
+                .get(a)
+                .map(|r| r.is_sleeping())
+                .unwrap_or(false)
+            {
+                continue;
+            }
+            for hit in c.iter().filter(|hit| !hit.sensor) {
+                if solved.insert(if a.id() < hit.with.id() {
+                    (a, hit.with)
+                } else {
+                    (hit.with, a)
+                }) {
+                    contacts.push((a, hit.with, hit.normal, hit.depth, &hit.points));
+                }
+            }
+        }
+
+        // Displacement applied so far, so that a body touching several colliders at once is
+        // not pushed out once per contact.
+        let mut moved: HashMap<Entity, Vec2<f64>> = HashMap::new();
+        // Normal and friction impulses applied so far at each contact point, along with the
+        // normal speed the point should separate at.
+        let mut applied: Vec<Vec<(f64, f64, f64)>> = vec![Vec::new(); contacts.len()];
+
+        for _ in 0..config.solver_iterations.max(1) {
+            for (i, &(a, b, n, depth, points)) in contacts.iter().enumerate() {
+                let inv_a = rigid_bodies.get(a).map(|r| r.inverse_mass()).unwrap_or(0.0);
+                let inv_b = rigid_bodies.get(b).map(|r| r.inverse_mass()).unwrap_or(0.0);
+                let inv = inv_a + inv_b;
+                if inv == 0.0 {
+                    continue;
+                }
+                let offset = moved.get(&a).cloned().unwrap_or_default()
+                    - moved.get(&b).cloned().unwrap_or_default();
+                let depth = depth - offset.dot(n);
+                if depth > 0.0 {
+                    for (e, share) in [(a, inv_a / inv), (b, -inv_b / inv)].iter() {
+                        if *share != 0.0 {
+                            let v = rigid_bodies.get(*e).unwrap().unlocked(n * depth * *share);
+                            *moved.entry(*e).or_default() += v;
+                            if let Some(t) = transforms.get_mut(*e) {
+                                t.position += v;
+                            }
+                        }
+                    }
+                }
+
+                // Off-centre contacts turn the bodies, each point gets its own impulse.
+                let position = |e| transforms.get(e).map(|t: &Transform| t.position);
+                let mut arms: Vec<_> = points
+                    .iter()
+                    .map(|p| {
+                        (
+                            position(a).map(|c| *p - c).unwrap_or_default(),
+                            position(b).map(|c| *p - c).unwrap_or_default(),
+                        )
+                    })
+                    .collect();
+                if arms.is_empty() {
+                    arms.push((Vec2::default(), Vec2::default()));
+                }
+                // `CarrySystem` moves riders along the surface of kinematic platforms, only the
+                // platform motion along the normal is left to the contact.
+                let relative =
+                    |rigid_bodies: &WriteStorage<RigidBody>, ra: Vec2<f64>, rb: Vec2<f64>| {
+                        let velocity = |e, ridden, arm| {
+                            rigid_bodies
+                                .get(e)
+                                .map(|r: &RigidBody| {
+                                    if ridden && r.body_type == BodyType::Kinematic {
+                                        n * r.velocity.dot(n)
+                                    } else {
+                                        r.velocity_at(arm)
+                                    }
+                                })
+                                .unwrap_or_default()
+                        };
+                        velocity(a, Side::from_normal(-n) == Side::Bottom, ra)
+                            - velocity(b, Side::from_normal(n) == Side::Bottom, rb)
+                    };
+                let material = |e| {
+                    colliders
+                        .get(e)
+                        .map(|c: &Collider| (c.restitution(), c.friction()))
+                        .unwrap_or((0.0, 0.0))
+                };
+                let (restitution_a, friction_a) = material(a);
+                let (restitution_b, friction_b) = material(b);
+                let inertia = |e| {
+                    rigid_bodies
+                        .get(e)
+                        .map(|r: &RigidBody| r.inverse_inertia())
+                        .unwrap_or(0.0)
+                };
+                let (ia, ib) = (inertia(a), inertia(b));
+                // Inverse of the mass felt at the contact point along `axis`.
+                let effective = |axis: Vec2<f64>, ra: Vec2<f64>, rb: Vec2<f64>| {
+                    inv + ra.cross(axis).powi(2) * ia + rb.cross(axis).powi(2) * ib
+                };
+                let apply = |rigid_bodies: &mut WriteStorage<RigidBody>,
+                             impulse: Vec2<f64>,
+                             ra: Vec2<f64>,
+                             rb: Vec2<f64>| {
+                    if let Some(r) = rigid_bodies.get_mut(a) {
+                        r.velocity += r.unlocked(impulse * inv_a);
+                        r.angular_velocity += ra.cross(impulse) * ia;
+                    }
+                    if let Some(r) = rigid_bodies.get_mut(b) {
+                        r.velocity -= r.unlocked(impulse * inv_b);
+                        r.angular_velocity -= rb.cross(impulse) * ib;
+                    }
+                };
+
+                if applied[i].is_empty() {
+                    // Bounces are set by the speed the bodies met at, before any impulse.
+                    applied[i] = arms
+                        .iter()
+                        .map(|&(ra, rb)| {
+                            let vn = relative(&rigid_bodies, ra, rb).dot(n);
+                            (0.0, 0.0, -restitution_a.max(restitution_b) * vn.min(0.0))
+                        })
+                        .collect();
+                }
+                // Only the totals are clamped, a later iteration may take back part of what an
+                // earlier one pushed too hard. Friction goes first, bounded by the normal impulses
+                // so far, so that the bodies are left not moving into each other.
+                let t = n.perpendicular();
+                for (&(ra, rb), (normal, friction, _)) in arms.iter().zip(applied[i].iter_mut()) {
+                    let bound = friction_a.max(friction_b) * *normal;
+                    let vt = relative(&rigid_bodies, ra, rb).dot(t);
+                    let total = (*friction - vt / effective(t, ra, rb)).clamp(-bound, bound);
+                    apply(&mut rigid_bodies, t * (total - *friction), ra, rb);
+                    *friction = total;
+                }
+                if let [(ra1, rb1), (ra2, rb2)] = arms[..] {
+                    // Both ends of an edge are solved together, one after the other they would
+                    // rock the body back and forth.
+                    let coupling = |p: usize, q: usize| {
+                        let ((ra, rb), (sa, sb)) = (arms[p], arms[q]);
+                        inv + ra.cross(n) * sa.cross(n) * ia + rb.cross(n) * sb.cross(n) * ib
+                    };
+                    let k = [
+                        [coupling(0, 0), coupling(0, 1)],
+                        [coupling(1, 0), coupling(1, 1)],
+                    ];
+                    let (p1, p2) = (applied[i][0], applied[i][1]);
+                    let speeds = [
+                        relative(&rigid_bodies, ra1, rb1).dot(n)
+                            - p1.2
+                            - k[0][0] * p1.0
+                            - k[0][1] * p2.0,
+                        relative(&rigid_bodies, ra2, rb2).dot(n)
+                            - p2.2
+                            - k[1][0] * p1.0
+                            - k[1][1] * p2.0,
+                    ];
+                    let totals = separate(k, speeds);
+                    apply(&mut rigid_bodies, n * (totals[0] - p1.0), ra1, rb1);
+                    apply(&mut rigid_bodies, n * (totals[1] - p2.0), ra2, rb2);
+                    applied[i][0].0 = totals[0];
+                    applied[i][1].0 = totals[1];
+                } else {
+                    for (&(ra, rb), (normal, _, bounce)) in arms.iter().zip(applied[i].iter_mut()) {
+                        let vn = relative(&rigid_bodies, ra, rb).dot(n);
+                        let total = (*normal + (*bounce - vn) / effective(n, ra, rb)).max(0.0);
+                        apply(&mut rigid_bodies, n * (total - *normal), ra, rb);
+                        *normal = total;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Non negative impulses `x` leaving two contact points separating, `k * x + speeds` being their
+/// speeds along the normal afterwards and `k` the inverse of the mass felt at the points.
+fn separate(k: [[f64; 2]; 2], speeds: [f64; 2]) -> [f64; 2] {
+    let det = k[0][0] * k[1][1] - k[0][1] * k[1][0];
+    if det.abs() > 1e-9 * k[0][0] * k[1][1] {
+        let x = [
+            (k[0][1] * speeds[1] - k[1][1] * speeds[0]) / det,
+            (k[1][0] * speeds[0] - k[0][0] * speeds[1]) / det,
+        ];
+        if x[0] >= 0.0 && x[1] >= 0.0 {
+            return x;
+        }
+    }
+    // One of the points is already separating.
+    let x = -speeds[0] / k[0][0];
+    if x >= 0.0 && k[1][0] * x + speeds[1] >= 0.0 {
+        return [x, 0.0];
+    }
+    let x = -speeds[1] / k[1][1];
+    if x >= 0.0 && k[0][1] * x + speeds[0] >= 0.0 {
+        return [0.0, x];
+    }
+    [0.0, 0.0]
+}
+
+#[cfg(test)]
+use super::{ColliderBuilder, ColliderType};
+#[cfg(test)]
+use crate::physics::RigidBodyBuilder;
+
+/// Runs a single collision step over 10x10 boxes, returning their positions and velocities.
+#[cfg(test)]
+fn resolve(
+    bodies: Vec<(Vec2<f64>, Vec2<f64>, RigidBodyBuilder, ColliderBuilder)>,
+) -> Vec<(Vec2<f64>, Vec2<f64>)> {
+    use super::{BroadPhaseSystem, CollisionFilter, CollisionSystem};
+    use crate::transform::TransformBuilder;
+    use specs::shred::RunNow;
+
+    let mut world = World::new();
+    world.register::<Collider>();
+    world.register::<Collisions>();
+    world.register::<CollisionFilter>();
+    world.register::<RigidBody>();
+    world.register::<Transform>();
+    world.insert(crate::physics::PhysicsConfig::default());
+    let mut broad_phase = BroadPhaseSystem::default();
+    RunNow::setup(&mut broad_phase, &mut world);
+    RunNow::setup(&mut CollisionSystem, &mut world);
+    let entities: Vec<_> = bodies
+        .into_iter()
+        .map(|(position, velocity, body, collider)| {
+            let mut body = body.build();
+            body.velocity = velocity;
+            world
+                .create_entity()
+                .with(TransformBuilder::new().position(position).build())
+                .with(body)
+                .with(
+                    collider
+                        .collider_type(ColliderType::Rect(10.0, 10.0))
+                        .build(),
+                )
+                .with(Collisions::default())
+                .build()
+        })
+        .collect();
+    broad_phase.run_now(&world);
+    CollisionSystem.run_now(&world);
+    RepultionSystem.run_now(&world);
+
+    let transforms = world.read_storage::<Transform>();
+    let rigid_bodies = world.read_storage::<RigidBody>();
+    entities
+        .iter()
+        .map(|e| {
+            (
+                transforms.get(*e).unwrap().position,
+                rigid_bodies.get(*e).unwrap().velocity,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn heavier_body_pushes_lighter_one() {
+    let res = resolve(vec![
+        (
+            Vec2::from((0.0, 0.0)),
+            Vec2::from((3.0, 0.0)),
+            RigidBodyBuilder::new().set_mass(30.0),
+            ColliderBuilder::new(),
+        ),
+        (
+            Vec2::from((8.0, 0.0)),
+            Vec2::default(),
+            RigidBodyBuilder::new().set_mass(10.0),
+            ColliderBuilder::new(),
+        ),
+    ]);
+
+    // 2 units of overlap, split 1:3.
+    assert!((res[0].0.x + 0.5).abs() < 1e-9, "{:?}", res);
+    assert!((res[1].0.x - 9.5).abs() < 1e-9, "{:?}", res);
+    // Perfectly inelastic, momentum is kept.
+    assert!((res[0].1.x - 2.25).abs() < 1e-9, "{:?}", res);
+    assert!((res[1].1.x - 2.25).abs() < 1e-9, "{:?}", res);
+}
+
+#[test]
+fn restitution_bounces_back() {
+    let res = resolve(vec![
+        (
+            Vec2::from((0.0, 0.0)),
+            Vec2::from((3.0, 0.0)),
+            RigidBodyBuilder::new().set_mass(10.0),
+            ColliderBuilder::new().restitution(1.0),
+        ),
+        (
+            Vec2::from((9.0, 0.0)),
+            Vec2::from((-3.0, 0.0)),
+            RigidBodyBuilder::new().set_mass(10.0),
+            ColliderBuilder::new(),
+        ),
+    ]);
+
+    assert!((res[0].1.x + 3.0).abs() < 1e-9, "{:?}", res);
+    assert!((res[1].1.x - 3.0).abs() < 1e-9, "{:?}", res);
+}
+
+#[test]
+fn friction_is_bounded_by_normal_impulse() {
+    let slide = |friction| {
+        resolve(vec![
+            (
+                Vec2::from((0.0, 9.0)),
+                Vec2::from((3.0, -1.0)),
+                RigidBodyBuilder::new().set_mass(10.0),
+                ColliderBuilder::new().friction(friction),
+            ),
+            (
+                Vec2::default(),
+                Vec2::default(),
+                RigidBodyBuilder::new().body_type(BodyType::Static),
+                ColliderBuilder::new(),
+            ),
+        ])[0]
+    };
+
+    let (position, velocity) = slide(0.5);
+    assert!((position.y - 10.0).abs() < 1e-9, "{:?}", position);
+    assert_eq!(velocity.y, 0.0);
+    assert!((velocity.x - 2.5).abs() < 1e-9, "{:?}", velocity);
+    assert_eq!(slide(0.0).1.x, 3.0);
+    assert_eq!(slide(10.0).1.x, 0.0);
+}
+
+#[test]
+fn kinematic_body_pushes_without_slowing_down() {
+    let res = resolve(vec![
+        (
+            Vec2::from((0.0, 0.0)),
+            Vec2::from((3.0, 0.0)),
+            RigidBodyBuilder::new().body_type(BodyType::Kinematic),
+            ColliderBuilder::new(),
+        ),
+        (
+            Vec2::from((8.0, 0.0)),
+            Vec2::default(),
+            RigidBodyBuilder::new().set_mass(10.0),
+            ColliderBuilder::new(),
+        ),
+    ]);
+
+    assert_eq!(res[0], (Vec2::from((0.0, 0.0)), Vec2::from((3.0, 0.0))));
+    assert_eq!(res[1], (Vec2::from((10.0, 0.0)), Vec2::from((3.0, 0.0))));
+}
+
+/// Drops a body with `friction` on static `ground`, returning its snapshot after `steps` steps.
+#[cfg(test)]
+fn roll(
+    ground: (Vec2<f64>, ColliderType),
+    body: (Vec2<f64>, ColliderType),
+    friction: f64,
+    steps: usize,
+) -> crate::testing::Snapshot {
+    let mut h = crate::testing::Harness::new();
+    h.add_collider(
+        ground.0,
+        ColliderBuilder::new().collider_type(ground.1).build(),
+    );
+    let (position, shape) = body;
+    let e = h.add_body(
+        position,
+        RigidBodyBuilder::new().inertia(shape.inertia(1.0)).build(),
+        ColliderBuilder::new()
+            .collider_type(shape)
+            .friction(friction)
+            .build(),
+    );
+    h.idle(steps);
+    h.snapshot(e)
+}
+
+#[test]
+fn crate_tips_over_ledge() {
+    let s = roll(
+        (Vec2::from((-50.0, 0.0)), ColliderType::Rect(100.0, 10.0)),
+        (Vec2::from((3.0, 10.0)), ColliderType::Rect(10.0, 10.0)),
+        1.0,
+        30,
+    );
+
+    // Its centre is past the edge, it turns clockwise while falling off.
+    assert!(s.rotation < -0.2, "{:?}", s);
+    assert!(s.position.x > 3.0, "{:?}", s);
+}
+
+#[test]
+fn crate_rests_flat() {
+    let s = roll(
+        (Vec2::default(), ColliderType::Rect(100.0, 10.0)),
+        (Vec2::from((0.0, 10.0)), ColliderType::Rect(10.0, 10.0)),
+        0.5,
+        600,
+    );
+
+    assert!(s.rotation.abs() < 1e-9, "{:?}", s);
+    assert!(s.position.x.abs() < 1e-9, "{:?}", s);
+    assert!((s.position.y - 10.0).abs() < 0.5, "{:?}", s);
+}
+
+#[test]
+fn wheel_rolls_down_slope() {
+    let slope = ColliderType::Segment(Vec2::from((-200.0, 100.0)), Vec2::from((200.0, -100.0)));
+    let s = roll(
+        (Vec2::default(), slope),
+        (Vec2::from((0.0, 12.0)), ColliderType::Circle(10.0)),
+        1.0,
+        60,
+    );
+    let (v, w) = (s.velocity.unwrap(), s.angular_velocity.unwrap());
+
+    assert!(v.x > 0.0 && s.rotation < 0.0, "{:?}", s);
+    // Rolling without slipping, the contact point stands still and a disc accelerates at two
+    // thirds of what it would sliding without friction.
+    assert!((v.module() - w.abs() * 10.0).abs() < 2.0, "{:?} {}", v, w);
+    let sliding = 594.0 / 5f64.sqrt();
+    assert!((v.module() - sliding * 2.0 / 3.0).abs() < 5.0, "{:?}", v);
+}
@@ -0,0 +1,336 @@
+use super::Aabb;
+use math::Vec2;
+
+/// Result of a narrow phase test, `normal` points toward the first shape, along which it has to
+/// be moved by `depth` to stop overlapping the second one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    pub normal: Vec2<f64>,
+    pub depth: f64,
+}
+
+impl Contact {
+    pub fn from_translation(v: Vec2<f64>) -> Self {
+        let depth = v.module();
+        Contact {
+            normal: if depth > 0.0 { v / depth } else { v },
+            depth,
+        }
+    }
+
+    /// Minimum translation vector pushing the first shape out of the second one.
+    pub fn translation(&self) -> Vec2<f64> {
+        self.normal * self.depth
+    }
+}
+
+/// A collider placed in world space.
+#[derive(Clone, Debug)]
+pub enum Shape {
+    Circle(Vec2<f64>, f64),
+    Polygon(Vec<Vec2<f64>>),
+    Capsule(Vec2<f64>, Vec2<f64>, f64),
+    Segment(Vec2<f64>, Vec2<f64>),
+    /// Open polyline, each of its segments is tested on its own.
+    Chain(Vec<Vec2<f64>>),
+}
+
+impl Shape {
+    pub fn aabb(&self) -> Aabb {
+        let (points, radius) = self.core();
+        let mut min = points[0];
+        let mut max = points[0];
+        for p in &points {
+            min = Vec2::from((min.x.min(p.x), min.y.min(p.y)));
+            max = Vec2::from((max.x.max(p.x), max.y.max(p.y)));
+        }
+        Aabb {
+            min: min - radius,
+            max: max + radius,
+        }
+    }
+
+    /// Extent of the shape along `axis`.
+    pub fn project(&self, axis: Vec2<f64>) -> (f64, f64) {
+        let (points, radius) = self.core();
+        project(&points, radius, axis)
+    }
+
+    /// Distance along the normalized `direction` at which a ray cast from `origin` enters the
+    /// shape, with the surface normal there. Rays starting inside hit at distance 0.
+    pub fn raycast(&self, origin: Vec2<f64>, direction: Vec2<f64>) -> Option<(f64, Vec2<f64>)> {
+        if let Shape::Chain(points) = self {
+            return points
+                .windows(2)
+                .filter_map(|w| ray_segment(origin, direction, w[0], w[1]))
+                .fold(None, closest);
+        }
+        let (points, radius) = self.core();
+        match (points.len(), radius > 0.0) {
+            (1, _) => ray_circle(origin, direction, points[0], radius),
+            (2, true) if points[0] == points[1] => ray_circle(origin, direction, points[0], radius),
+            (2, true) => {
+                let (a, b) = (points[0], points[1]);
+                let n = (b - a).perpendicular().normalize() * radius;
+                vec![
+                    ray_circle(origin, direction, a, radius),
+                    ray_circle(origin, direction, b, radius),
+                    ray_polygon(origin, direction, &[a + n, b + n, b - n, a - n]),
+                ]
+                .into_iter()
+                .flatten()
+                .fold(None, closest)
+            }
+            (2, false) => ray_segment(origin, direction, points[0], points[1]),
+            _ => ray_polygon(origin, direction, &points),
+        }
+    }
+
+    /// Every shape but chains is a convex hull of points inflated by a radius.
+    fn core(&self) -> (Vec<Vec2<f64>>, f64) {
+        match self {
+            Shape::Circle(c, r) => (vec![*c], *r),
+            Shape::Polygon(points) | Shape::Chain(points) => (points.clone(), 0.0),
+            Shape::Capsule(a, b, r) => (vec![*a, *b], *r),
+            Shape::Segment(a, b) => (vec![*a, *b], 0.0),
+        }
+    }
+}
+
+fn edge_normals(points: &[Vec2<f64>]) -> Vec<Vec2<f64>> {
+    if points.len() < 2 {
+        return Vec::new();
+    }
+    (0..points.len())
+        .map(|i| points[(i + 1) % points.len()] - points[i])
+        .filter(|e| e.amplitude_squared() > 0.0)
+        .map(|e| e.perpendicular().normalize())
+        .collect()
+}
+
+fn project(points: &[Vec2<f64>], radius: f64, axis: Vec2<f64>) -> (f64, f64) {
+    let (min, max) = points
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), p| {
+            let d = p.dot(axis);
+            (min.min(d), max.max(d))
+        });
+    (min - radius, max + radius)
+}
+
+fn closest(best: Option<(f64, Vec2<f64>)>, hit: (f64, Vec2<f64>)) -> Option<(f64, Vec2<f64>)> {
+    match best {
+        Some(b) if b.0 <= hit.0 => Some(b),
+        _ => Some(hit),
+    }
+}
+
+fn ray_circle(
+    origin: Vec2<f64>,
+    direction: Vec2<f64>,
+    center: Vec2<f64>,
+    radius: f64,
+) -> Option<(f64, Vec2<f64>)> {
+    let m = origin - center;
+    let b = m.dot(direction);
+    let c = m.dot(m) - radius * radius;
+    if c <= 0.0 {
+        return Some((0.0, -direction));
+    }
+    let disc = b * b - c;
+    if b > 0.0 || disc < 0.0 {
+        return None;
+    }
+    let t = -b - disc.sqrt();
+    Some((t, (origin + direction * t - center) / radius))
+}
+
+fn ray_segment(
+    origin: Vec2<f64>,
+    direction: Vec2<f64>,
+    a: Vec2<f64>,
+    b: Vec2<f64>,
+) -> Option<(f64, Vec2<f64>)> {
+    let e = b - a;
+    let denom = direction.cross(e);
+    if denom == 0.0 {
+        return None;
+    }
+    let w = a - origin;
+    let t = w.cross(e) / denom;
+    let s = w.cross(direction) / denom;
+    if t < 0.0 || !(0.0..=1.0).contains(&s) {
+        return None;
+    }
+    let n = e.perpendicular().normalize();
+    Some((t, if n.dot(direction) > 0.0 { -n } else { n }))
+}
+
+/// Clips the ray against every edge of a convex polygon.
+fn ray_polygon(
+    origin: Vec2<f64>,
+    direction: Vec2<f64>,
+    points: &[Vec2<f64>],
+) -> Option<(f64, Vec2<f64>)> {
+    let centroid = points.iter().fold(Vec2::default(), |a, p| a + *p) / points.len() as f64;
+    let mut enter = (f64::NEG_INFINITY, Vec2::default());
+    let mut exit = f64::INFINITY;
+    for (i, p) in points.iter().enumerate() {
+        let e = points[(i + 1) % points.len()] - *p;
+        if e.amplitude_squared() == 0.0 {
+            continue;
+        }
+        let mut n = e.perpendicular().normalize();
+        if (centroid - *p).dot(n) > 0.0 {
+            n = -n;
+        }
+        let dist = n.dot(origin - *p);
+        let denom = n.dot(direction);
+        if denom == 0.0 {
+            if dist > 0.0 {
+                return None;
+            }
+        } else {
+            let t = -dist / denom;
+            if denom < 0.0 {
+                if t > enter.0 {
+                    enter = (t, n);
+                }
+            } else {
+                exit = exit.min(t);
+            }
+        }
+    }
+
+    if enter.0 > exit || exit < 0.0 {
+        None
+    } else if enter.0 < 0.0 {
+        Some((0.0, -direction))
+    } else {
+        Some(enter)
+    }
+}
+
+/// Separating axis test between two shapes.
+pub fn collide(a: &Shape, b: &Shape) -> Option<Contact> {
+    match (a, b) {
+        (Shape::Chain(points), _) => deepest(points, |s| collide(s, b)).map(|(_, c)| c),
+        (_, Shape::Chain(points)) => deepest(points, |s| collide(a, s)).map(|(_, c)| c),
+        _ => {
+            let (a, ra) = a.core();
+            let (b, rb) = b.core();
+            sat(&a, ra, &b, rb)
+        }
+    }
+}
+
+/// Segment of a chain with the deepest contact.
+fn deepest<F>(points: &[Vec2<f64>], collide: F) -> Option<(Shape, Contact)>
+where
+    F: Fn(&Shape) -> Option<Contact>,
+{
+    points
+        .windows(2)
+        .map(|w| Shape::Segment(w[0], w[1]))
+        .filter_map(|s| collide(&s).map(|c| (s, c)))
+        .fold(None, |best, (s, c)| match best {
+            Some((bs, bc)) if bc.depth >= c.depth => Some((bs, bc)),
+            _ => Some((s, c)),
+        })
+}
+
+/// The closest features of two convex hulls are either a vertex and an edge or two vertices,
+/// so testing the edge normals and, for rounded shapes, every vertex to vertex direction is
+/// enough.
+fn sat(a: &[Vec2<f64>], ra: f64, b: &[Vec2<f64>], rb: f64) -> Option<Contact> {
+    let mut axes = edge_normals(a);
+    axes.extend(edge_normals(b));
+    if ra + rb > 0.0 {
+        for pa in a {
+            for pb in b {
+                let d = *pa - *pb;
+                if d.amplitude_squared() > 0.0 {
+                    axes.push(d.normalize());
+                }
+            }
+        }
+    }
+    if axes.is_empty() {
+        axes.push(Vec2::from((0.0, 1.0)));
+    }
+
+    let mut best: Option<Contact> = None;
+    for axis in axes {
+        let (a_min, a_max) = project(a, ra, axis);
+        let (b_min, b_max) = project(b, rb, axis);
+        let (depth, normal) = if a_max - b_min < b_max - a_min {
+            (a_max - b_min, -axis)
+        } else {
+            (b_max - a_min, axis)
+        };
+        if depth <= 0.0 {
+            return None;
+        }
+        if best.map(|c| depth < c.depth).unwrap_or(true) {
+            best = Some(Contact { normal, depth });
+        }
+    }
+
+    best
+}
+
+/// Points of `points` the furthest along `dir`, at most two for a convex hull.
+fn features(points: &[Vec2<f64>], dir: Vec2<f64>) -> Vec<Vec2<f64>> {
+    let max = points
+        .iter()
+        .map(|p| p.dot(dir))
+        .fold(f64::NEG_INFINITY, f64::max);
+    let mut res: Vec<_> = points
+        .iter()
+        .filter(|p| p.dot(dir) >= max - 1e-6)
+        .cloned()
+        .collect();
+    res.dedup();
+    res
+}
+
+/// Where `a` and `b` touch, given the `contact` found between them.
+pub fn contact_points(a: &Shape, b: &Shape, contact: &Contact) -> Vec<Vec2<f64>> {
+    match (a, b) {
+        (Shape::Chain(points), _) => deepest(points, |s| collide(s, b))
+            .map(|(s, c)| contact_points(&s, b, &c))
+            .unwrap_or_default(),
+        (_, Shape::Chain(points)) => deepest(points, |s| collide(a, s))
+            .map(|(s, c)| contact_points(a, &s, &c))
+            .unwrap_or_default(),
+        _ => {
+            let n = contact.normal;
+            let (a, ra) = a.core();
+            let (b, rb) = b.core();
+            let fa: Vec<_> = features(&a, -n).into_iter().map(|p| p - n * ra).collect();
+            let fb: Vec<_> = features(&b, n).into_iter().map(|p| p + n * rb).collect();
+
+            if fa.len() == 1 {
+                return fa;
+            }
+            if fb.len() == 1 {
+                return fb;
+            }
+
+            // Two facing edges, keep the part of `b`'s edge overlapping `a`'s one.
+            let t = n.perpendicular();
+            let range = |f: &Vec<Vec2<f64>>| {
+                let (x, y) = (f[0].dot(t), f[1].dot(t));
+                (x.min(y), x.max(y))
+            };
+            let (a_min, a_max) = range(&fa);
+            let (b_min, b_max) = range(&fb);
+            let level = fb[0].dot(n);
+            let mut res = vec![t * a_min.max(b_min) + n * level];
+            if a_max.min(b_max) > a_min.max(b_min) {
+                res.push(t * a_max.min(b_max) + n * level);
+            }
+            res
+        }
+    }
+}
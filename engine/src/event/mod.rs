@@ -26,4 +26,4 @@ macro_rules! generate_kp {
     };
 }
 
-generate_kp!(KeyA, KeyD, KeyW, KeyK, ShiftLeft, ArrowLeft, ArrowRight, ArrowUp);
+generate_kp!(KeyA, KeyD, KeyW, KeyS, KeyK, ShiftLeft, ArrowLeft, ArrowRight, ArrowUp, ArrowDown);
@@ -1,8 +1,12 @@
 mod camera;
-mod collider;
+pub mod collider;
 mod event;
-mod physics;
+pub mod physics;
 mod render;
+mod schedule;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod time;
 mod transform;
 // mod macros;
 
@@ -17,20 +21,24 @@ pub mod prelude {
     // pub use script_lang::*;
 }
 pub mod types {
-    pub use crate::collider::ColliderType;
+    pub use crate::collider::{
+        ColliderType, CollisionEnded, CollisionLayers, CollisionStarted, ContactState, Side,
+    };
+    pub use crate::physics::{BodyType, JointType, PhysicsConfig};
+    pub use crate::schedule::Stage;
+    pub use specs::shrev::EventChannel;
 }
 pub mod builder {
     pub use crate::collider::ColliderBuilder;
-    pub use crate::physics::RigidBodyBuilder;
+    pub use crate::physics::{CharacterControllerBuilder, JointBuilder, RigidBodyBuilder};
     pub use crate::render::animation::AnimationBuilder;
     pub use crate::render::sprite::SpriteBuilder;
+    pub use crate::schedule::GameBuilder;
     pub use crate::transform::TransformBuilder;
 }
 pub mod components {
-    pub use crate::collider::{AntiLayer1, Layer1};
-    pub use crate::collider::{AntiLayer2, Layer2};
-    pub use crate::collider::{Collider, Collisions};
-    pub use crate::physics::RigidBody;
+    pub use crate::collider::{Collider, CollisionFilter, Collisions};
+    pub use crate::physics::{CharacterController, Effector, Joint, RigidBody};
     pub use crate::render::sprite::Sprite;
     pub use crate::transform::Transform;
 }
@@ -38,57 +46,183 @@ pub use camera::Camera;
 pub use event::KeyPress;
 pub use math;
 pub use render::{color::Color, image::Image};
+pub use schedule::{GameBuilder, Stage};
 pub use specs;
+pub use time::{Interpolation, Time};
 
 use specs::prelude::*;
+use std::time::Duration;
 
 pub struct Game {
     pub world: specs::shred::World,
-    physics: physics::PhysicsSystem,
-    layer1: collider::Layer1System,
-    layer2: collider::Layer2System,
-    repulsor: collider::RepultionSystem,
-    sprite: render::sprite::SpriteUpdaterSystem,
+    snapshot: transform::TransformSnapshotSystem,
+    stages: Vec<(Stage, Dispatcher<'static, 'static>)>,
 }
 
 impl Game {
     pub fn new() -> Self {
-        let mut world = World::new();
-        world.insert(event::KeyPress::default());
-        world.insert(camera::Camera::default());
-        world.register::<collider::Collider>();
-        world.register::<collider::Collisions>();
-        world.register::<physics::RigidBody>();
-        world.register::<transform::Transform>();
-        world.register::<render::sprite::Sprite>();
-
-        let mut physics = physics::PhysicsSystem;
-        let mut layer1 = collider::Layer1System;
-        let mut layer2 = collider::Layer2System;
-        let mut repulsor = collider::RepultionSystem;
-        let mut sprite = render::sprite::SpriteUpdaterSystem;
-
-        specs::shred::RunNow::setup(&mut physics, &mut world);
-        specs::shred::RunNow::setup(&mut layer1, &mut world);
-        specs::shred::RunNow::setup(&mut layer2, &mut world);
-        specs::shred::RunNow::setup(&mut repulsor, &mut world);
-        specs::shred::RunNow::setup(&mut sprite, &mut world);
-
-        Game {
-            world,
-            physics,
-            layer1,
-            layer2,
-            repulsor,
-            sprite,
+        GameBuilder::new().build()
+    }
+
+    /// Registers a named collision layer and returns its `CollisionFilter` bit.
+    pub fn register_layer(&mut self, name: &str) -> u32 {
+        self.world
+            .write_resource::<collider::CollisionLayers>()
+            .register(name)
+    }
+
+    pub fn layer(&self, name: &str) -> Option<u32> {
+        self.world
+            .read_resource::<collider::CollisionLayers>()
+            .get(name)
+    }
+
+    /// Advances the simulation by `elapsed` wall-clock time, running as many fixed steps as
+    /// fit in the accumulator and updating the `Interpolation` resource for rendering.
+    pub fn tick(&mut self, elapsed: Duration) {
+        self.run_stage(Stage::Input);
+
+        let step = self
+            .world
+            .read_resource::<physics::PhysicsConfig>()
+            .time_step;
+        let steps = {
+            let mut time = self.world.write_resource::<time::Time>();
+            time.set_step(step);
+            time.advance(elapsed)
+        };
+        for _ in 0..steps {
+            self.step();
         }
+
+        let alpha = self.world.read_resource::<time::Time>().alpha();
+        self.world.write_resource::<time::Interpolation>().0 = alpha;
+
+        self.world.maintain();
+        self.run_stage(Stage::Render);
     }
 
-    pub fn run_sys(&mut self) {
-        self.physics.run_now(&mut self.world);
-        self.layer1.run_now(&mut self.world);
-        self.layer2.run_now(&mut self.world);
-        self.repulsor.run_now(&mut self.world);
-        self.sprite.run_now(&mut self.world);
+    /// Runs a single fixed step, regardless of the accumulated time.
+    pub fn step(&mut self) {
+        self.snapshot.run_now(&self.world);
+        for stage in Stage::ALL.iter().filter(|s| s.is_fixed()) {
+            self.run_stage(*stage);
+        }
     }
+
+    fn run_stage(&mut self, stage: Stage) {
+        for (s, dispatcher) in &mut self.stages {
+            if *s == stage {
+                dispatcher.dispatch(&self.world);
+            }
+        }
+    }
+}
+
+#[test]
+fn falling_body_is_independent_of_frame_rate() {
+    fn fall(hz: u64) -> f64 {
+        let mut game = Game::new();
+        let e = game
+            .world
+            .create_entity()
+            .with(transform::Transform::default())
+            .with(physics::RigidBodyBuilder::new().set_mass(1.0).build())
+            .build();
+        for _ in 0..hz {
+            game.tick(Duration::from_nanos(1_000_000_000 / hz));
+        }
+        let transforms = game.world.read_storage::<transform::Transform>();
+        let alpha = game.world.read_resource::<time::Interpolation>().0;
+        transforms.get(e).unwrap().interpolated_position(alpha).y
+    }
+
+    let y60 = fall(60);
+    let y144 = fall(144);
+    assert!((y60 - y144).abs() < 10.0, "{} != {}", y60, y144);
+}
+
+#[test]
+fn publishes_collision_start_and_end() {
+    use crate::collider::{ColliderBuilder, ColliderType, CollisionEnded, CollisionStarted};
+    use specs::shrev::EventChannel;
+
+    let mut game = Game::new();
+    let mut started = game
+        .world
+        .fetch_mut::<EventChannel<CollisionStarted>>()
+        .register_reader();
+    let mut ended = game
+        .world
+        .fetch_mut::<EventChannel<CollisionEnded>>()
+        .register_reader();
+    let ground = game
+        .world
+        .create_entity()
+        .with(transform::Transform::default())
+        .with(
+            ColliderBuilder::new()
+                .collider_type(ColliderType::Rect(100.0, 20.0))
+                .build(),
+        )
+        .build();
+    let body = game
+        .world
+        .create_entity()
+        .with(
+            transform::TransformBuilder::new()
+                .position(math::Vec2::from((0.0, 30.0)))
+                .build(),
+        )
+        .with(physics::RigidBodyBuilder::new().set_mass(1.0).build())
+        .with(
+            ColliderBuilder::new()
+                .collider_type(ColliderType::Rect(10.0, 10.0))
+                .build(),
+        )
+        .with(collider::Collisions::default())
+        .build();
+
+    let step = game
+        .world
+        .read_resource::<physics::PhysicsConfig>()
+        .time_step;
+    for _ in 0..60 {
+        game.tick(step);
+    }
+    {
+        let channel = game.world.fetch::<EventChannel<CollisionStarted>>();
+        let events: Vec<_> = channel.read(&mut started).collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].entity, body);
+        assert_eq!(events[0].collision.with, ground);
+    }
+
+    game.world.delete_entity(ground).unwrap();
+    game.tick(step);
+    let channel = game.world.fetch::<EventChannel<CollisionEnded>>();
+    let events: Vec<_> = channel.read(&mut ended).collect();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].collision.with, ground);
+}
+
+#[test]
+fn physics_config_changes_at_runtime() {
+    let mut game = Game::new();
+    let e = game
+        .world
+        .create_entity()
+        .with(transform::Transform::default())
+        .with(physics::RigidBodyBuilder::new().set_mass(1.0).build())
+        .build();
+    {
+        let mut config = game.world.write_resource::<physics::PhysicsConfig>();
+        config.gravity = -config.gravity;
+        config.set_rate(30);
+    }
+    game.tick(Duration::from_secs(1) / 10);
+
+    assert_eq!(game.world.read_resource::<time::Time>().steps(), 3);
+    let transforms = game.world.read_storage::<transform::Transform>();
+    assert!(transforms.get(e).unwrap().position.y > 0.0);
 }
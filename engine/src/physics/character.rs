@@ -0,0 +1,456 @@
+use super::{PhysicsConfig, RigidBody};
+use crate::collider::Collisions;
+use math::Vec2;
+use specs::prelude::*;
+use std::time::Duration;
+
+pub struct CharacterControllerBuilder {
+    run_speed: f64,
+    acceleration: f64,
+    air_acceleration: f64,
+    jump_height: f64,
+    jump_cut: f64,
+    coyote_time: Duration,
+    jump_buffer: Duration,
+    wall_slide_speed: f64,
+    max_slope: f64,
+}
+
+impl Default for CharacterControllerBuilder {
+    fn default() -> Self {
+        CharacterControllerBuilder::new()
+    }
+}
+
+impl CharacterControllerBuilder {
+    pub fn new() -> Self {
+        CharacterControllerBuilder {
+            run_speed: 180.0,
+            acceleration: 1800.0,
+            air_acceleration: 900.0,
+            jump_height: 64.0,
+            jump_cut: 0.5,
+            coyote_time: Duration::from_millis(100),
+            jump_buffer: Duration::from_millis(100),
+            wall_slide_speed: 60.0,
+            max_slope: std::f64::consts::FRAC_PI_4,
+        }
+    }
+
+    pub fn run_speed(mut self, speed: f64) -> Self {
+        self.run_speed = speed;
+        self
+    }
+
+    /// How fast the run speed is reached on the ground, and in the air.
+    pub fn acceleration(mut self, ground: f64, air: f64) -> Self {
+        self.acceleration = ground;
+        self.air_acceleration = air;
+        self
+    }
+
+    /// Apex of a jump held until it, above the take off point.
+    pub fn jump_height(mut self, height: f64) -> Self {
+        self.jump_height = height;
+        self
+    }
+
+    /// Fraction of the upward velocity kept when the jump is released early.
+    pub fn jump_cut(mut self, cut: f64) -> Self {
+        self.jump_cut = cut;
+        self
+    }
+
+    /// How long after running off a ledge a jump is still allowed.
+    pub fn coyote_time(mut self, time: Duration) -> Self {
+        self.coyote_time = time;
+        self
+    }
+
+    /// How long before landing a jump press is remembered.
+    pub fn jump_buffer(mut self, time: Duration) -> Self {
+        self.jump_buffer = time;
+        self
+    }
+
+    /// Fastest fall while pushing against a wall.
+    pub fn wall_slide_speed(mut self, speed: f64) -> Self {
+        self.wall_slide_speed = speed;
+        self
+    }
+
+    /// Steepest walkable ground, in radians. Anything steeper is a wall.
+    pub fn max_slope(mut self, angle: f64) -> Self {
+        self.max_slope = angle;
+        self
+    }
+
+    pub fn build(self) -> CharacterController {
+        CharacterController {
+            run: 0.0,
+            jump: false,
+            run_speed: self.run_speed,
+            acceleration: self.acceleration,
+            air_acceleration: self.air_acceleration,
+            jump_height: self.jump_height,
+            jump_cut: self.jump_cut,
+            coyote_time: self.coyote_time,
+            jump_buffer: self.jump_buffer,
+            wall_slide_speed: self.wall_slide_speed,
+            max_slope: self.max_slope,
+            ground: None,
+            wall: None,
+            since_ground: None,
+            since_jump: None,
+            jump_held: false,
+            jumping: false,
+        }
+    }
+}
+
+/// Moves the `RigidBody` of a platformer character from the intent set by the game, `run` and
+/// `jump`, see `CharacterSystem`.
+pub struct CharacterController {
+    /// Run direction and strength, in `[-1, 1]`.
+    pub run: f64,
+    /// Whether the jump button is held.
+    pub jump: bool,
+    pub run_speed: f64,
+    pub acceleration: f64,
+    pub air_acceleration: f64,
+    pub jump_height: f64,
+    pub jump_cut: f64,
+    pub coyote_time: Duration,
+    pub jump_buffer: Duration,
+    pub wall_slide_speed: f64,
+    pub max_slope: f64,
+    ground: Option<Vec2<f64>>,
+    wall: Option<Vec2<f64>>,
+    /// Time since leaving the ground, `None` once jumped.
+    since_ground: Option<Duration>,
+    /// Time since the jump was pressed, `None` once used.
+    since_jump: Option<Duration>,
+    jump_held: bool,
+    jumping: bool,
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        CharacterControllerBuilder::new().build()
+    }
+}
+
+impl CharacterController {
+    pub fn on_ground(&self) -> bool {
+        self.ground.is_some()
+    }
+
+    /// Normal of the ground stood on.
+    pub fn ground_normal(&self) -> Option<Vec2<f64>> {
+        self.ground
+    }
+
+    /// Normal of the wall touched while in the air.
+    pub fn wall_normal(&self) -> Option<Vec2<f64>> {
+        self.wall
+    }
+
+    /// Rising from a jump.
+    pub fn is_jumping(&self) -> bool {
+        self.jumping
+    }
+}
+
+impl Component for CharacterController {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Turns the intent of each `CharacterController` into velocity, from the contacts of the last
+/// step. Ground is told apart from walls by `max_slope`, relative to gravity.
+pub struct CharacterSystem;
+impl<'a> System<'a> for CharacterSystem {
+    type SystemData = (
+        Read<'a, PhysicsConfig>,
+        ReadStorage<'a, Collisions>,
+        WriteStorage<'a, CharacterController>,
+        WriteStorage<'a, RigidBody>,
+    );
+
+    fn run(&mut self, (config, collisions, mut controllers, mut rigid_bodies): Self::SystemData) {
+        let dt = config.time_step;
+        let seconds = dt.as_secs_f64();
+        for (c, ch, r) in (&collisions, &mut controllers, &mut rigid_bodies).join() {
+            let gravity = config.gravity * r.gravity_scale;
+            let up = if gravity.module() > 0.0 {
+                -gravity.normalize()
+            } else {
+                Vec2::from((0.0, 1.0))
+            };
+            let right = -up.perpendicular();
+
+            let flat = ch.max_slope.cos();
+            let solid = || c.iter().filter(|hit| !hit.sensor);
+            if ch.jumping && r.velocity.dot(up) <= 0.0 {
+                ch.jumping = false;
+            }
+            // Contacts found before taking off are still around for a step.
+            ch.ground = if ch.jumping {
+                None
+            } else {
+                solid()
+                    .map(|hit| hit.normal)
+                    .filter(|n| n.dot(up) >= flat)
+                    .fold(None, |best: Option<Vec2<f64>>, n| match best {
+                        Some(b) if b.dot(up) >= n.dot(up) => Some(b),
+                        _ => Some(n),
+                    })
+            };
+            ch.wall = if ch.ground.is_some() {
+                None
+            } else {
+                solid()
+                    .map(|hit| hit.normal)
+                    .find(|n| n.dot(up).abs() < flat)
+            };
+
+            // Coyote time and jump buffering.
+            if ch.ground.is_some() {
+                ch.since_ground = Some(Duration::default());
+            } else {
+                ch.since_ground = ch.since_ground.map(|t| t + dt);
+            }
+            if ch.jump && !ch.jump_held {
+                ch.since_jump = Some(Duration::default());
+            } else {
+                ch.since_jump = ch.since_jump.map(|t| t + dt);
+            }
+            ch.jump_held = ch.jump;
+
+            let speed = r.velocity.dot(up);
+            if ch.since_jump.map(|t| t <= ch.jump_buffer).unwrap_or(false)
+                && ch
+                    .since_ground
+                    .map(|t| t <= ch.coyote_time)
+                    .unwrap_or(false)
+            {
+                let jump = (2.0 * gravity.module() * ch.jump_height).sqrt();
+                r.velocity += up * (jump - speed);
+                ch.since_jump = None;
+                ch.since_ground = None;
+                ch.ground = None;
+                ch.jumping = true;
+            } else if ch.jumping && !ch.jump && speed > 0.0 {
+                // Released early, a shorter jump.
+                r.velocity -= up * speed * (1.0 - ch.jump_cut);
+                ch.jumping = false;
+            }
+
+            // Run along the ground, or sideways in the air.
+            let (along, acceleration) = match ch.ground {
+                Some(n) => (-n.perpendicular(), ch.acceleration),
+                None => (right, ch.air_acceleration),
+            };
+            let current = r.velocity.dot(along);
+            let target = ch.run.clamp(-1.0, 1.0) * ch.run_speed;
+            let step = acceleration * seconds;
+            let next = if (target - current).abs() <= step {
+                target
+            } else {
+                current + step * (target - current).signum()
+            };
+            r.velocity += along * (next - current);
+
+            if let Some(n) = ch.ground {
+                // Standing still on slopes, gravity only presses against the ground.
+                let slide = gravity - n * gravity.dot(n);
+                r.force -= slide * r.mass;
+            } else if let Some(n) = ch.wall {
+                // Gravity is yet to be applied this step.
+                let fall = gravity.module() * seconds - r.velocity.dot(up);
+                if ch.run * n.dot(right) < 0.0 && fall > ch.wall_slide_speed {
+                    r.velocity += up * (fall - ch.wall_slide_speed);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+struct Level {
+    h: crate::testing::Harness,
+    character: Entity,
+}
+
+/// A character standing at `x` on the ground at height 0, next to `geometry`.
+#[cfg(test)]
+fn level(x: f64, geometry: Vec<(Vec2<f64>, crate::collider::ColliderType)>) -> Level {
+    use super::RigidBodyBuilder;
+    use crate::collider::{ColliderBuilder, ColliderType};
+
+    let mut h = crate::testing::Harness::new();
+    let ground = (Vec2::from((0.0, -10.0)), ColliderType::Rect(400.0, 20.0));
+    for (position, shape) in geometry.into_iter().chain(Some(ground)) {
+        h.add_collider(
+            position,
+            ColliderBuilder::new().collider_type(shape).build(),
+        );
+    }
+    let character = h.add_body(
+        Vec2::from((x, 15.0)),
+        RigidBodyBuilder::new().build(),
+        ColliderBuilder::new()
+            .collider_type(ColliderType::Rect(14.0, 30.0))
+            .build(),
+    );
+    h.world()
+        .write_storage::<CharacterController>()
+        .insert(character, CharacterController::default())
+        .unwrap();
+    let mut level = Level { h, character };
+    level.play(0.0, false, 10);
+    level
+}
+
+#[cfg(test)]
+impl Level {
+    /// Steps with the given intent, returning the position after each step.
+    fn play(&mut self, run: f64, jump: bool, steps: usize) -> Vec<Vec2<f64>> {
+        (0..steps)
+            .map(|_| {
+                {
+                    let mut controllers = self.h.world().write_storage::<CharacterController>();
+                    let ch = controllers.get_mut(self.character).unwrap();
+                    ch.run = run;
+                    ch.jump = jump;
+                }
+                self.h.frame(&[]);
+                self.position()
+            })
+            .collect()
+    }
+
+    fn position(&self) -> Vec2<f64> {
+        self.h.snapshot(self.character).position
+    }
+
+    fn velocity(&self) -> Vec2<f64> {
+        self.h.snapshot(self.character).velocity.unwrap()
+    }
+
+    fn on_ground(&self) -> bool {
+        self.h
+            .game
+            .world
+            .read_storage::<CharacterController>()
+            .get(self.character)
+            .unwrap()
+            .on_ground()
+    }
+}
+
+#[cfg(test)]
+fn apex(path: &[Vec2<f64>]) -> f64 {
+    path.iter().map(|p| p.y).fold(f64::NEG_INFINITY, f64::max) - 15.0
+}
+
+#[test]
+fn runs_and_jumps_to_its_height() {
+    let mut level = level(0.0, vec![]);
+    assert!(level.on_ground());
+    level.play(1.0, false, 6);
+    assert!((level.velocity().x - 180.0).abs() < 1e-4);
+    level.play(0.0, false, 6);
+    assert!(level.velocity().x.abs() < 1e-4);
+
+    let path = level.play(0.0, true, 60);
+    assert!((apex(&path) - 64.0).abs() < 2.0, "{}", apex(&path));
+    assert!(level.on_ground());
+}
+
+#[test]
+fn released_jump_is_shorter() {
+    let mut level = level(0.0, vec![]);
+    let mut path = level.play(0.0, true, 5);
+    path.extend(level.play(0.0, false, 60));
+    assert!(apex(&path) < 40.0, "{}", apex(&path));
+}
+
+#[test]
+fn jumps_shortly_after_leaving_ledge_or_before_landing() {
+    use crate::collider::ColliderType;
+
+    // Ground ends at x = 200, with a ledge up to x = 100 above it.
+    let jump_after = |steps| {
+        let ledge = (Vec2::from((0.0, 10.0)), ColliderType::Rect(200.0, 20.0));
+        let mut level = level(80.0, vec![ledge]);
+        level.play(0.0, false, 30);
+        while level.on_ground() {
+            level.play(1.0, false, 1);
+        }
+        level.play(1.0, false, steps);
+        level.play(1.0, true, 1);
+        level.velocity().y > 0.0
+    };
+    assert!(jump_after(3));
+    assert!(!jump_after(12));
+
+    // Pressed in the air right before landing.
+    let mut falling = level(0.0, vec![]);
+    falling.play(0.0, true, 1);
+    falling.play(0.0, false, 1);
+    while falling.velocity().y > -100.0 || falling.position().y > 20.0 {
+        falling.play(0.0, false, 1);
+    }
+    let path = falling.play(0.0, true, 10);
+    assert!(path.iter().any(|p| p.y > path[0].y + 5.0), "{:?}", path);
+}
+
+#[test]
+fn slides_down_walls_slowly() {
+    use crate::collider::ColliderType;
+
+    let wall = (Vec2::from((20.0, 100.0)), ColliderType::Rect(20.0, 200.0));
+    let mut level = level(0.0, vec![wall]);
+    level.play(1.0, true, 20);
+    let path = level.play(1.0, false, 40);
+    assert!(path.iter().all(|p| p.x <= 3.0 + 1e-6));
+    assert!(level.velocity().y >= -60.0 - 1e-6, "{:?}", level.velocity());
+}
+
+#[test]
+fn stands_still_on_slopes() {
+    use crate::collider::ColliderType;
+
+    // 30 degrees.
+    let slope = (
+        Vec2::default(),
+        ColliderType::Segment(Vec2::from((-100.0, 57.7)), Vec2::from((100.0, -57.7))),
+    );
+    let mut gentle = level(0.0, vec![slope]);
+    let rest = gentle.play(0.0, false, 30)[29];
+    assert!(gentle.on_ground());
+    let path = gentle.play(0.0, false, 60);
+    assert!(
+        (path[59] - rest).module() < 0.5,
+        "{:?} {:?}",
+        rest,
+        path[59]
+    );
+
+    // Too steep to stand on, dropped right above it.
+    let wall = (
+        Vec2::from((0.0, 100.0)),
+        ColliderType::Segment(Vec2::from((-50.0, 86.6)), Vec2::from((50.0, -86.6))),
+    );
+    let mut steep = level(-100.0, vec![wall]);
+    steep
+        .h
+        .world()
+        .write_storage::<crate::transform::Transform>()
+        .get_mut(steep.character)
+        .unwrap()
+        .position = Vec2::from((10.0, 110.0));
+    let path = steep.play(0.0, false, 10);
+    assert!(!steep.on_ground());
+    assert!(path[9].x > path[0].x && path[9].y < path[0].y, "{:?}", path);
+}
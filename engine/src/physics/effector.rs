@@ -0,0 +1,212 @@
+use super::{BodyType, PhysicsConfig, RigidBody};
+use crate::collider::{BroadPhase, Collider, CollisionFilter, Collisions};
+use crate::transform::Transform;
+use math::Vec2;
+use specs::prelude::*;
+
+/// Applies forces to the dynamic bodies overlapping, or resting on, the `Collider` of the same
+/// entity. Those whose layers are not in the effector `CollisionFilter` mask are left alone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Effector {
+    /// Constant force, such as wind.
+    Force(Vec2<f64>),
+    /// Drives bodies along `direction` at `speed`.
+    Conveyor { direction: Vec2<f64>, speed: f64 },
+    /// Force toward the entity position, pushing away when negative.
+    Point(f64),
+    /// Pushes bodies up against gravity proportionally to their submerged area, `density` being
+    /// a mass per unit area, and slows them down proportionally to `drag`.
+    Water { density: f64, drag: f64 },
+}
+
+impl Component for Effector {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Adds the force of every effector to the bodies in its area, right before they are integrated,
+/// waking them up. Bodies are looked up in the `BroadPhase`, where they are as of the last step.
+pub struct EffectorSystem;
+impl<'a> System<'a> for EffectorSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, PhysicsConfig>,
+        Read<'a, BroadPhase>,
+        ReadStorage<'a, Effector>,
+        ReadStorage<'a, Collider>,
+        ReadStorage<'a, CollisionFilter>,
+        ReadStorage<'a, Collisions>,
+        ReadStorage<'a, Transform>,
+        WriteStorage<'a, RigidBody>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            config,
+            broad_phase,
+            effectors,
+            colliders,
+            filters,
+            collisions,
+            transforms,
+            mut rigid_bodies,
+        ): Self::SystemData,
+    ) {
+        let dt = config.time_step.as_secs_f64();
+        for (area, effector, c1, t1) in (&entities, &effectors, &colliders, &transforms).join() {
+            let f1 = filters.get(area).cloned().unwrap_or_default();
+            let bounds = c1.aabb(t1);
+            for e in broad_phase.candidates(&bounds) {
+                let (c, t, r) = match (colliders.get(e), transforms.get(e), rigid_bodies.get_mut(e))
+                {
+                    (Some(c), Some(t), Some(r)) => (c, t, r),
+                    _ => continue,
+                };
+                let f = filters.get(e).cloned().unwrap_or_default();
+                if e == area
+                    || !entities.is_alive(e)
+                    || r.body_type != BodyType::Dynamic
+                    || !f1.collides_with(&f)
+                {
+                    continue;
+                }
+                let touching = collisions
+                    .get(e)
+                    .map(|c| c.with(area).is_some())
+                    .unwrap_or(false);
+                if !touching && c.collide_with(c1, t, t1).is_none() {
+                    continue;
+                }
+
+                let force = match *effector {
+                    Effector::Force(force) => force,
+                    Effector::Conveyor { direction, speed } => {
+                        let direction = direction.normalize();
+                        direction * (speed - r.velocity.dot(direction)) * r.mass / dt
+                    }
+                    Effector::Point(strength) => {
+                        let d = t1.position - t.position;
+                        let distance = d.module();
+                        if distance > 0.0 {
+                            d / distance * strength
+                        } else {
+                            Vec2::default()
+                        }
+                    }
+                    Effector::Water { density, drag } => {
+                        let body = c.aabb(t);
+                        let w = body.max.x.min(bounds.max.x) - body.min.x.max(bounds.min.x);
+                        let h = body.max.y.min(bounds.max.y) - body.min.y.max(bounds.min.y);
+                        let area = (body.max.x - body.min.x) * (body.max.y - body.min.y);
+                        if w <= 0.0 || h <= 0.0 || area <= 0.0 {
+                            continue;
+                        }
+                        let submerged = w * h;
+                        -config.gravity * density * submerged - r.velocity * drag * submerged / area
+                    }
+                };
+                if force != Vec2::default() {
+                    r.apply_force(r.unlocked(force));
+                }
+            }
+        }
+    }
+}
+
+/// Positions and velocities of `body` in an effector area, turned on once the body is indexed in
+/// the broad phase.
+#[cfg(test)]
+fn drift(
+    effector: Effector,
+    body: RigidBody,
+    start: Vec2<f64>,
+    steps: usize,
+) -> Vec<(Vec2<f64>, Vec2<f64>)> {
+    use crate::collider::{ColliderBuilder, ColliderType};
+
+    let mut h = crate::testing::Harness::new();
+    let area = h.add_collider(
+        Vec2::default(),
+        ColliderBuilder::new()
+            .collider_type(ColliderType::Rect(200.0, 200.0))
+            .sensor()
+            .build(),
+    );
+    let body = h.add_body(
+        start,
+        body,
+        ColliderBuilder::new()
+            .collider_type(ColliderType::Rect(10.0, 10.0))
+            .build(),
+    );
+    h.idle(1);
+    h.world()
+        .write_storage::<Effector>()
+        .insert(area, effector)
+        .unwrap();
+
+    h.idle(steps);
+    h.frames()[1..]
+        .iter()
+        .map(|f| {
+            let s = f.of(body).unwrap();
+            (s.position, s.velocity.unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn wind_and_conveyor_push_bodies() {
+    let wind = Effector::Force(Vec2::from((30.0, 0.0)));
+    let path = drift(wind, RigidBody::default(), Vec2::default(), 10);
+    assert!((path[9].1.x - 5.0).abs() < 1e-6);
+    // Even asleep.
+    let body = super::RigidBodyBuilder::new()
+        .gravity_scale(0.0)
+        .sleeping()
+        .build();
+    let path = drift(wind, body, Vec2::default(), 10);
+    assert!((path[9].1.x - 5.0).abs() < 1e-6);
+
+    let conveyor = Effector::Conveyor {
+        direction: Vec2::from((-1.0, 0.0)),
+        speed: 120.0,
+    };
+    let path = drift(conveyor, RigidBody::default(), Vec2::default(), 10);
+    assert!(path.iter().all(|(_, v)| (v.x + 120.0).abs() < 1e-9));
+    // Out of the area, bodies just fall.
+    let path = drift(conveyor, RigidBody::default(), Vec2::from((0.0, 200.0)), 10);
+    assert!(path.iter().all(|(_, v)| v.x == 0.0));
+}
+
+#[test]
+fn attractor_pulls_toward_center() {
+    let path = drift(
+        Effector::Point(30.0),
+        RigidBody::default(),
+        Vec2::from((50.0, 0.0)),
+        10,
+    );
+    assert!(path[9].1.x < 0.0);
+    let path = drift(
+        Effector::Point(-30.0),
+        RigidBody::default(),
+        Vec2::from((50.0, 0.0)),
+        10,
+    );
+    assert!(path[9].1.x > 0.0);
+}
+
+#[test]
+fn body_floats_half_submerged() {
+    // Unit mass, 100 units of area, neutral at 50 submerged.
+    let water = Effector::Water {
+        density: 0.02,
+        drag: 3.0,
+    };
+    let path = drift(water, RigidBody::default(), Vec2::from((0.0, 50.0)), 1200);
+
+    let (p, _) = path.last().unwrap();
+    assert!((p.y - 100.0).abs() < 0.5, "{:?}", p);
+}
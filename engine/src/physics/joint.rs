@@ -0,0 +1,349 @@
+use super::{BodyType, PhysicsConfig, RigidBody};
+use crate::transform::Transform;
+use math::Vec2;
+use specs::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JointType {
+    /// Keeps the anchors exactly this far apart, like a rigid rod.
+    Distance(f64),
+    /// Pulls the anchors toward `length` apart with a force proportional to the stretch.
+    Spring {
+        length: f64,
+        stiffness: f64,
+        damping: f64,
+    },
+    /// Keeps the anchors at most this far apart.
+    Rope(f64),
+    /// Pins the anchors together, the bodies being free to turn around them.
+    Revolute,
+}
+
+pub struct JointBuilder {
+    a: Entity,
+    b: Entity,
+    joint_type: JointType,
+    anchor_a: Vec2<f64>,
+    anchor_b: Vec2<f64>,
+}
+
+impl JointBuilder {
+    /// Joint between `a` and `b`. Either may lack a `RigidBody`, it then stays put.
+    pub fn new(a: Entity, b: Entity, joint_type: JointType) -> Self {
+        JointBuilder {
+            a,
+            b,
+            joint_type,
+            anchor_a: Vec2::default(),
+            anchor_b: Vec2::default(),
+        }
+    }
+
+    /// Attachment points, relative to the position of each entity.
+    pub fn anchors(mut self, a: Vec2<f64>, b: Vec2<f64>) -> Self {
+        self.anchor_a = a;
+        self.anchor_b = b;
+        self
+    }
+
+    pub fn build(self) -> Joint {
+        Joint {
+            a: self.a,
+            b: self.b,
+            joint_type: self.joint_type,
+            anchor_a: self.anchor_a,
+            anchor_b: self.anchor_b,
+        }
+    }
+}
+
+/// Constraint between two entities, usually added to an entity of its own.
+pub struct Joint {
+    pub a: Entity,
+    pub b: Entity,
+    pub joint_type: JointType,
+    pub anchor_a: Vec2<f64>,
+    pub anchor_b: Vec2<f64>,
+}
+
+impl Component for Joint {
+    type Storage = DenseVecStorage<Self>;
+}
+
+fn anchor(t: &Transform, local: Vec2<f64>) -> Vec2<f64> {
+    local.scale(t.scale).rotate(t.rotation) + t.position
+}
+
+/// Solves joints after bodies are integrated, moving them back in place and removing the
+/// relative velocity along the constraint, `PhysicsConfig::solver_iterations` times. Springs
+/// add to the force of their bodies instead, felt on the next step.
+pub struct JointSystem;
+impl<'a> System<'a> for JointSystem {
+    type SystemData = (
+        Read<'a, PhysicsConfig>,
+        ReadStorage<'a, Joint>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, RigidBody>,
+    );
+
+    fn run(&mut self, (config, joints, mut transforms, mut rigid_bodies): Self::SystemData) {
+        // Joints between sleeping bodies are left alone, their forces would wake them up.
+        let joints: Vec<_> = (&joints)
+            .join()
+            .filter(|joint| {
+                [joint.a, joint.b].iter().any(|e| {
+                    rigid_bodies
+                        .get(*e)
+                        .map(|r| r.body_type != BodyType::Static && !r.is_sleeping())
+                        .unwrap_or(false)
+                })
+            })
+            .collect();
+
+        for joint in &joints {
+            if let JointType::Spring {
+                length: rest,
+                stiffness,
+                damping,
+            } = joint.joint_type
+            {
+                let s = match state(joint, &transforms, &rigid_bodies) {
+                    Some(s) => s,
+                    None => continue,
+                };
+                let length = s.offset.module();
+                if length == 0.0 {
+                    continue;
+                }
+                let n = s.offset / length;
+                let f = n * (stiffness * (length - rest) + damping * s.relative.dot(n));
+                if let Some(r) = rigid_bodies.get_mut(joint.a) {
+                    r.force += r.unlocked(f);
+                    r.torque += s.arm_a.cross(f);
+                }
+                if let Some(r) = rigid_bodies.get_mut(joint.b) {
+                    r.force -= r.unlocked(f);
+                    r.torque -= s.arm_b.cross(f);
+                }
+            }
+        }
+
+        for _ in 0..config.solver_iterations.max(1) {
+            for joint in &joints {
+                let s = match state(joint, &transforms, &rigid_bodies) {
+                    Some(s) => s,
+                    None => continue,
+                };
+                let length = s.offset.module();
+
+                // Position error to remove and relative velocity to cancel, from `a` to `b`.
+                let (error, drift) = match joint.joint_type {
+                    JointType::Revolute => (s.offset, s.relative),
+                    JointType::Distance(target) if length > 0.0 => {
+                        let n = s.offset / length;
+                        (n * (length - target), n * s.relative.dot(n))
+                    }
+                    JointType::Rope(max) if length > max => {
+                        let n = s.offset / length;
+                        (n * (length - max), n * s.relative.dot(n).max(0.0))
+                    }
+                    _ => continue,
+                };
+
+                let inverse = |e| {
+                    rigid_bodies
+                        .get(e)
+                        .map(|r: &RigidBody| (r.inverse_mass(), r.inverse_inertia()))
+                        .unwrap_or((0.0, 0.0))
+                };
+                let (inv_a, inv_ia) = inverse(joint.a);
+                let (inv_b, inv_ib) = inverse(joint.b);
+                let (ra, rb) = (s.arm_a, s.arm_b);
+                let inv = inv_a + inv_b;
+                // Impulse applied at the anchor of `a`, and opposite at the one of `b`, that
+                // changes their relative velocity by `v`.
+                let impulse = |v: Vec2<f64>| match joint.joint_type {
+                    JointType::Revolute => {
+                        let k11 = inv + inv_ia * ra.y * ra.y + inv_ib * rb.y * rb.y;
+                        let k12 = -inv_ia * ra.x * ra.y - inv_ib * rb.x * rb.y;
+                        let k22 = inv + inv_ia * ra.x * ra.x + inv_ib * rb.x * rb.x;
+                        let det = k11 * k22 - k12 * k12;
+                        if det == 0.0 {
+                            return Vec2::default();
+                        }
+                        Vec2::from(((k22 * v.x - k12 * v.y) / det, (k11 * v.y - k12 * v.x) / det))
+                    }
+                    _ => {
+                        let n = s.offset / length;
+                        let k = inv + inv_ia * ra.cross(n).powi(2) + inv_ib * rb.cross(n).powi(2);
+                        if k == 0.0 {
+                            return Vec2::default();
+                        }
+                        n * (v.dot(n) / k)
+                    }
+                };
+
+                let (p, q) = (impulse(drift), impulse(error));
+                for (e, arm, sign) in [(joint.a, ra, 1.0), (joint.b, rb, -1.0)].iter() {
+                    let r = match rigid_bodies.get_mut(*e) {
+                        Some(r) => r,
+                        None => continue,
+                    };
+                    let (inv_mass, inv_inertia) = (r.inverse_mass(), r.inverse_inertia());
+                    r.velocity += r.unlocked(p * (inv_mass * sign));
+                    r.angular_velocity += arm.cross(p) * inv_inertia * sign;
+                    let correction = r.unlocked(q * (inv_mass * sign));
+                    let t = transforms.get_mut(*e).unwrap();
+                    t.position += correction;
+                    t.rotation += arm.cross(q) * inv_inertia * sign;
+                }
+            }
+        }
+    }
+}
+
+/// Where the anchors of a joint are and how they move.
+struct State {
+    /// From the anchor of `a` to the one of `b`.
+    offset: Vec2<f64>,
+    /// Velocity of the anchor of `b` relative to the one of `a`.
+    relative: Vec2<f64>,
+    /// From the position of each body to its anchor.
+    arm_a: Vec2<f64>,
+    arm_b: Vec2<f64>,
+}
+
+fn state(
+    joint: &Joint,
+    transforms: &WriteStorage<Transform>,
+    rigid_bodies: &WriteStorage<RigidBody>,
+) -> Option<State> {
+    let ta = transforms.get(joint.a)?;
+    let tb = transforms.get(joint.b)?;
+    let arm_a = anchor(ta, joint.anchor_a) - ta.position;
+    let arm_b = anchor(tb, joint.anchor_b) - tb.position;
+    let velocity = |e, arm| {
+        rigid_bodies
+            .get(e)
+            .map(|r| r.velocity_at(arm))
+            .unwrap_or_default()
+    };
+    Some(State {
+        offset: tb.position + arm_b - ta.position - arm_a,
+        relative: velocity(joint.b, arm_b) - velocity(joint.a, arm_a),
+        arm_a,
+        arm_b,
+    })
+}
+
+/// Hangs `body`, starting at `start`, by its `anchor` from a pivot at the origin, returning
+/// its snapshot after each step.
+#[cfg(test)]
+fn hang(
+    joint_type: JointType,
+    anchor: Vec2<f64>,
+    body: RigidBody,
+    start: Vec2<f64>,
+    steps: usize,
+) -> Vec<crate::testing::Snapshot> {
+    use crate::collider::ColliderBuilder;
+
+    let mut h = crate::testing::Harness::new();
+    let pivot = h.world().create_entity().with(Transform::default()).build();
+    let bob = h.add_body(start, body, ColliderBuilder::new().build());
+    h.world()
+        .create_entity()
+        .with(
+            JointBuilder::new(pivot, bob, joint_type)
+                .anchors(Vec2::default(), anchor)
+                .build(),
+        )
+        .build();
+    h.idle(steps);
+    h.frames()
+        .iter()
+        .map(|f| f.of(bob).unwrap().clone())
+        .collect()
+}
+
+/// Positions of a point mass hanging from the origin.
+#[cfg(test)]
+fn bob(joint_type: JointType, start: Vec2<f64>, steps: usize) -> Vec<Vec2<f64>> {
+    hang(
+        joint_type,
+        Vec2::default(),
+        RigidBody::default(),
+        start,
+        steps,
+    )
+    .iter()
+    .map(|s| s.position)
+    .collect()
+}
+
+#[test]
+fn pendulum_keeps_its_length() {
+    let path = bob(JointType::Distance(50.0), Vec2::from((50.0, 0.0)), 300);
+
+    for p in &path {
+        assert!((p.module() - 50.0).abs() < 1e-6, "{:?}", p);
+    }
+    // It swings to the other side.
+    assert!(path.iter().any(|p| p.x < -40.0));
+}
+
+#[test]
+fn rope_only_pulls_when_taut() {
+    let path = bob(JointType::Rope(50.0), Vec2::from((0.0, -10.0)), 120);
+
+    assert!(path[10].y < -10.0);
+    assert!(path.iter().all(|p| p.module() < 50.0 + 1e-6));
+    assert!((path.last().unwrap().y + 50.0).abs() < 1e-6);
+}
+
+#[test]
+fn spring_settles_under_gravity() {
+    let stiffness = 1800.0;
+    let path = bob(
+        JointType::Spring {
+            length: 20.0,
+            stiffness,
+            damping: 30.0,
+        },
+        Vec2::from((0.0, -20.0)),
+        600,
+    );
+
+    // Unit mass, stretched until the spring pulls as hard as gravity.
+    let stretch = PhysicsConfig::default().gravity.y / stiffness;
+    assert!(
+        (path.last().unwrap().y - (-20.0 + stretch)).abs() < 0.1,
+        "{:?}",
+        path.last()
+    );
+}
+
+#[test]
+fn revolute_pins_anchors_together() {
+    let path = bob(JointType::Revolute, Vec2::from((0.0, 0.0)), 60);
+    assert!(path.iter().all(|p| p.module() < 1e-9));
+}
+
+#[test]
+fn bar_swings_around_its_end() {
+    use super::RigidBodyBuilder;
+    use crate::collider::ColliderType;
+
+    // Pinned by its left end, level with the pivot.
+    let bar = ColliderType::Rect(40.0, 10.0);
+    let body = RigidBodyBuilder::new().inertia(bar.inertia(1.0)).build();
+    let end = Vec2::from((-20.0, 0.0));
+    let path = hang(JointType::Revolute, end, body, Vec2::from((20.0, 0.0)), 60);
+
+    for s in &path {
+        let pinned = end.rotate(s.rotation) + s.position;
+        assert!(pinned.module() < 1e-6, "{:?}", s);
+    }
+    // It turns clockwise, below the pivot and up the other side.
+    assert!(path.iter().any(|s| s.rotation < -2.5), "{:?}", path.last());
+}
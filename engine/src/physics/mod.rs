@@ -0,0 +1,552 @@
+mod character;
+mod effector;
+mod joint;
+pub mod query;
+mod sleep;
+
+pub use character::{CharacterController, CharacterControllerBuilder, CharacterSystem};
+pub use effector::{Effector, EffectorSystem};
+pub use joint::{Joint, JointBuilder, JointSystem, JointType};
+pub use sleep::SleepSystem;
+
+use super::transform::Transform;
+use math::Vec2;
+use specs::prelude::*;
+use std::time::Duration;
+
+/// World wide physics settings, can be changed at runtime. Distances are in pixels and
+/// durations in seconds, so velocities are in pixels per second.
+pub struct PhysicsConfig {
+    /// Acceleration of every body, scaled by `RigidBody::gravity_scale`.
+    pub gravity: Vec2<f64>,
+    /// Duration of a fixed step.
+    pub time_step: Duration,
+    /// Number of passes over the contacts when resolving collisions.
+    pub solver_iterations: u32,
+    /// Speed under which a body is considered at rest.
+    pub sleep_velocity: f64,
+    /// Angular speed under which a body is considered at rest, in radians per second.
+    pub sleep_angular_velocity: f64,
+    /// How long a body has to stay at rest before falling asleep.
+    pub sleep_time: Duration,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        PhysicsConfig {
+            gravity: Vec2::from((0.0, -594.0)),
+            time_step: Duration::from_nanos(1_000_000_000 / 60),
+            solver_iterations: 4,
+            sleep_velocity: 3.0,
+            sleep_angular_velocity: 0.05,
+            sleep_time: Duration::from_millis(500),
+        }
+    }
+}
+
+impl PhysicsConfig {
+    /// Sets `time_step` to run `hz` steps per second, at least one.
+    pub fn set_rate(&mut self, hz: u32) {
+        self.time_step = Duration::from_nanos(1_000_000_000 / hz.max(1) as u64);
+    }
+}
+
+/// How a `RigidBody` is moved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BodyType {
+    /// Moved by forces, gravity and collisions.
+    Dynamic,
+    /// Moved by its velocity only, pushes dynamic bodies out of its way.
+    Kinematic,
+    /// Never moves.
+    Static,
+}
+
+/// Bodies only turn once given an `inertia`, usually `ColliderType::inertia` of their collider for
+/// their mass, such as `.inertia(shape.inertia(mass))` for a crate. Characters and other upright
+/// bodies need no setup.
+pub struct RigidBodyBuilder {
+    body_type: BodyType,
+    mass: Option<f64>,
+    linear_damping: f64,
+    drag: Vec2<f64>,
+    gravity_scale: f64,
+    max_speed: f64,
+    inertia: f64,
+    angular_damping: f64,
+    lock_x: bool,
+    lock_y: bool,
+    ccd: bool,
+    can_sleep: bool,
+    sleeping: bool,
+}
+
+impl RigidBodyBuilder {
+    pub fn new() -> Self {
+        RigidBodyBuilder {
+            body_type: BodyType::Dynamic,
+            mass: None,
+            linear_damping: 0.0,
+            drag: Vec2::default(),
+            gravity_scale: 1.0,
+            max_speed: f64::INFINITY,
+            inertia: f64::INFINITY,
+            angular_damping: 0.0,
+            lock_x: false,
+            lock_y: false,
+            ccd: false,
+            can_sleep: true,
+            sleeping: false,
+        }
+    }
+
+    pub fn body_type(mut self, body_type: BodyType) -> Self {
+        self.body_type = body_type;
+        self
+    }
+
+    /// Must be positive for dynamic bodies.
+    pub fn set_mass(mut self, mass: f64) -> Self {
+        self.mass = Some(mass);
+        self
+    }
+
+    /// Rate at which the velocity decays, per second.
+    pub fn linear_damping(mut self, damping: f64) -> Self {
+        self.linear_damping = damping;
+        self
+    }
+
+    /// Rate at which the velocity decays, per second, on each axis.
+    pub fn drag(mut self, drag: Vec2<f64>) -> Self {
+        self.drag = drag;
+        self
+    }
+
+    pub fn gravity_scale(mut self, scale: f64) -> Self {
+        self.gravity_scale = scale;
+        self
+    }
+
+    pub fn max_speed(mut self, speed: f64) -> Self {
+        self.max_speed = speed;
+        self
+    }
+
+    /// Resistance to turning, see `ColliderType::inertia`. Bodies never turn by default, infinite
+    /// inertia keeps them upright.
+    pub fn inertia(mut self, inertia: f64) -> Self {
+        self.inertia = inertia;
+        self
+    }
+
+    /// Rate at which the angular velocity decays, per second.
+    pub fn angular_damping(mut self, damping: f64) -> Self {
+        self.angular_damping = damping;
+        self
+    }
+
+    /// The body never moves horizontally.
+    pub fn lock_x(mut self) -> Self {
+        self.lock_x = true;
+        self
+    }
+
+    /// The body never moves vertically.
+    pub fn lock_y(mut self) -> Self {
+        self.lock_y = true;
+        self
+    }
+
+    /// Stops the body at its first impact along the path of each step, see `CcdSystem`.
+    pub fn ccd(mut self) -> Self {
+        self.ccd = true;
+        self
+    }
+
+    /// The body stays awake even when at rest, see `SleepSystem`.
+    pub fn never_sleep(mut self) -> Self {
+        self.can_sleep = false;
+        self
+    }
+
+    /// Starts asleep, until something touches it.
+    pub fn sleeping(mut self) -> Self {
+        self.sleeping = true;
+        self
+    }
+
+    pub fn build(self) -> RigidBody {
+        let mass = self.mass.unwrap_or(1.0);
+        assert!(
+            self.body_type != BodyType::Dynamic || mass > 0.0,
+            "dynamic bodies need a positive mass, use BodyType::Static for immovable ones"
+        );
+        RigidBody {
+            body_type: self.body_type,
+            mass,
+            force: Vec2::default(),
+            torque: 0.0,
+            acceleration: Vec2::default(),
+            velocity: Vec2::default(),
+            linear_damping: self.linear_damping,
+            drag: self.drag,
+            gravity_scale: self.gravity_scale,
+            max_speed: self.max_speed,
+            angular_velocity: 0.0,
+            inertia: self.inertia,
+            angular_damping: self.angular_damping,
+            lock_x: self.lock_x,
+            lock_y: self.lock_y,
+            ccd: self.ccd,
+            can_sleep: self.can_sleep,
+            sleeping: self.sleeping,
+            idle: Duration::default(),
+        }
+    }
+}
+
+pub struct RigidBody {
+    pub body_type: BodyType,
+    /// Only used by dynamic bodies, for which it is positive.
+    pub mass: f64,
+    /// Sum of the forces applied during the step, cleared once integrated.
+    pub force: Vec2<f64>,
+    /// Sum of the torques applied through `apply_force_at_point` during the step.
+    pub torque: f64,
+    pub acceleration: Vec2<f64>,
+    pub velocity: Vec2<f64>,
+    pub linear_damping: f64,
+    pub drag: Vec2<f64>,
+    pub gravity_scale: f64,
+    pub max_speed: f64,
+    /// Counter-clockwise, in radians per second.
+    pub angular_velocity: f64,
+    pub inertia: f64,
+    pub angular_damping: f64,
+    pub lock_x: bool,
+    pub lock_y: bool,
+    pub ccd: bool,
+    pub can_sleep: bool,
+    sleeping: bool,
+    /// How long the body has been at rest.
+    idle: Duration,
+}
+
+impl Default for RigidBody {
+    fn default() -> Self {
+        RigidBodyBuilder::new().build()
+    }
+}
+
+impl RigidBody {
+    /// Force pushing the body during the next step, such as thrust.
+    pub fn apply_force(&mut self, force: Vec2<f64>) {
+        self.force += force;
+        self.wake();
+    }
+
+    /// Force applied at `point`, relative to the body position, turning it as well.
+    pub fn apply_force_at_point(&mut self, force: Vec2<f64>, point: Vec2<f64>) {
+        self.apply_force(force);
+        self.torque += point.cross(force);
+    }
+
+    /// Instant change of momentum, such as a jump or a hit.
+    pub fn apply_impulse(&mut self, impulse: Vec2<f64>) {
+        self.velocity += self.unlocked(impulse * self.inverse_mass());
+        self.wake();
+    }
+
+    /// Impulse applied at `point`, relative to the body position, turning it as well.
+    pub fn apply_impulse_at_point(&mut self, impulse: Vec2<f64>, point: Vec2<f64>) {
+        self.apply_impulse(impulse);
+        self.angular_velocity += point.cross(impulse) * self.inverse_inertia();
+    }
+
+    pub fn set_velocity(&mut self, velocity: Vec2<f64>) {
+        self.velocity = self.unlocked(velocity);
+        self.wake();
+    }
+
+    /// Sleeping bodies are skipped by `PhysicsSystem` and `CollisionSystem`.
+    pub fn is_sleeping(&self) -> bool {
+        self.sleeping
+    }
+
+    pub fn wake(&mut self) {
+        self.sleeping = false;
+        self.idle = Duration::default();
+    }
+
+    pub(crate) fn sleep(&mut self) {
+        self.sleeping = true;
+        self.force = Vec2::default();
+        self.torque = 0.0;
+        self.acceleration = Vec2::default();
+        self.velocity = Vec2::default();
+        self.angular_velocity = 0.0;
+    }
+
+    /// `v` without the components along locked axes.
+    pub fn unlocked(&self, v: Vec2<f64>) -> Vec2<f64> {
+        Vec2::from((
+            if self.lock_x { 0.0 } else { v.x },
+            if self.lock_y { 0.0 } else { v.y },
+        ))
+    }
+
+    /// Zero for bodies collisions can't move.
+    pub fn inverse_mass(&self) -> f64 {
+        if self.body_type == BodyType::Dynamic {
+            1.0 / self.mass
+        } else {
+            0.0
+        }
+    }
+
+    /// Zero for bodies that never turn.
+    pub fn inverse_inertia(&self) -> f64 {
+        if self.body_type == BodyType::Dynamic && self.inertia > 0.0 && self.inertia.is_finite() {
+            1.0 / self.inertia
+        } else {
+            0.0
+        }
+    }
+
+    /// Velocity of the point at `offset` from the body position, spin included.
+    pub fn velocity_at(&self, offset: Vec2<f64>) -> Vec2<f64> {
+        self.velocity + offset.perpendicular() * self.angular_velocity
+    }
+}
+
+impl Component for RigidBody {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Moves bodies by their velocity, integrating forces and gravity over `PhysicsConfig::time_step`.
+///
+/// Acceleration is held constant over a step and integrated exactly, so ballistic arcs do not
+/// depend on the step size and bodies whose forces balance gravity do not creep.
+pub struct PhysicsSystem;
+
+impl<'a> System<'a> for PhysicsSystem {
+    type SystemData = (
+        Read<'a, PhysicsConfig>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, RigidBody>,
+    );
+
+    fn run(&mut self, (config, mut transforms, mut rigid_bodies): Self::SystemData) {
+        let dt = config.time_step.as_secs_f64();
+        for (t, r) in (&mut transforms, &mut rigid_bodies).join() {
+            match r.body_type {
+                BodyType::Static => {
+                    r.velocity = Vec2::default();
+                    r.angular_velocity = 0.0;
+                    continue;
+                }
+                BodyType::Kinematic => {
+                    r.force = Vec2::default();
+                    r.torque = 0.0;
+                    r.acceleration = Vec2::default();
+                    r.velocity = r.unlocked(r.velocity);
+                    t.position += r.velocity * dt;
+                    t.rotation += r.angular_velocity * dt;
+                    continue;
+                }
+                BodyType::Dynamic => {}
+            }
+            if r.sleeping {
+                if r.force == Vec2::default()
+                    && r.velocity == Vec2::default()
+                    && r.torque == 0.0
+                    && r.angular_velocity == 0.0
+                {
+                    continue;
+                }
+                r.wake();
+            }
+
+            r.acceleration =
+                r.unlocked(r.force * r.inverse_mass() + config.gravity * r.gravity_scale);
+            r.force = Vec2::default();
+            r.velocity += r.acceleration * dt;
+            r.angular_velocity += r.torque * r.inverse_inertia() * dt;
+            r.torque = 0.0;
+            if r.inverse_inertia() == 0.0 {
+                r.angular_velocity = 0.0;
+            }
+
+            r.velocity *= (-r.linear_damping * dt).exp();
+            r.velocity.x *= (-r.drag.x * dt).exp();
+            r.velocity.y *= (-r.drag.y * dt).exp();
+            let speed = r.velocity.module();
+            if speed > r.max_speed {
+                r.velocity *= r.max_speed / speed;
+            }
+            r.velocity = r.unlocked(r.velocity);
+
+            r.angular_velocity *= (-r.angular_damping * dt).exp();
+
+            t.position += (r.velocity - r.acceleration * (dt / 2.0)) * dt;
+            t.rotation += r.angular_velocity * dt;
+        }
+    }
+}
+
+/// Runs `steps` steps over a single body, pushed by `force` during each of them.
+#[cfg(test)]
+fn simulate(
+    config: PhysicsConfig,
+    body: RigidBody,
+    steps: usize,
+    force: Vec2<f64>,
+) -> (Vec2<f64>, Vec2<f64>) {
+    use specs::shred::RunNow;
+
+    let mut world = World::new();
+    world.insert(config);
+    world.register::<Transform>();
+    world.register::<RigidBody>();
+    let e = world
+        .create_entity()
+        .with(Transform::default())
+        .with(body)
+        .build();
+    for _ in 0..steps {
+        world.write_storage::<RigidBody>().get_mut(e).unwrap().force += force;
+        PhysicsSystem.run_now(&world);
+    }
+
+    let position = world.read_storage::<Transform>().get(e).unwrap().position;
+    let velocity = world.read_storage::<RigidBody>().get(e).unwrap().velocity;
+    (position, velocity)
+}
+
+#[cfg(test)]
+fn fly(body: RigidBodyBuilder, velocity: Vec2<f64>, steps: usize) -> (Vec2<f64>, Vec2<f64>) {
+    let mut body = body.set_mass(1.0).build();
+    body.velocity = velocity;
+    simulate(PhysicsConfig::default(), body, steps, Vec2::default())
+}
+
+#[test]
+fn drag_and_max_speed_slow_the_body() {
+    let (_, v) = fly(
+        RigidBodyBuilder::new()
+            .gravity_scale(0.0)
+            .drag(Vec2::from((2f64.ln(), 0.0))),
+        Vec2::from((8.0, 8.0)),
+        60,
+    );
+    assert!((v - Vec2::from((4.0, 8.0))).module() < 1e-6, "{:?}", v);
+
+    let (_, v) = fly(
+        RigidBodyBuilder::new().gravity_scale(0.0).max_speed(5.0),
+        Vec2::from((3.0, 4.0)),
+        1,
+    );
+    assert_eq!(v, Vec2::from((3.0, 4.0)));
+    let (_, v) = fly(
+        RigidBodyBuilder::new().gravity_scale(0.0).max_speed(2.5),
+        Vec2::from((3.0, 4.0)),
+        1,
+    );
+    assert!((v - Vec2::from((1.5, 2.0))).module() < 1e-9, "{:?}", v);
+}
+
+#[test]
+fn locked_axis_does_not_move() {
+    let (p, v) = fly(
+        RigidBodyBuilder::new().lock_y(),
+        Vec2::from((60.0, 60.0)),
+        60,
+    );
+    assert!((p - Vec2::from((60.0, 0.0))).module() < 1e-4, "{:?}", p);
+    assert_eq!(v.y, 0.0);
+
+    let (p, _) = fly(
+        RigidBodyBuilder::new().gravity_scale(-1.0),
+        Vec2::default(),
+        1,
+    );
+    assert!(p.y > 0.0);
+}
+
+#[test]
+fn only_dynamic_bodies_fall() {
+    let v = Vec2::from((60.0, 0.0));
+    let (p, _) = fly(
+        RigidBodyBuilder::new().body_type(BodyType::Kinematic),
+        v,
+        60,
+    );
+    assert!((p - Vec2::from((60.0, 0.0))).module() < 1e-4, "{:?}", p);
+    let (p, v) = fly(RigidBodyBuilder::new().body_type(BodyType::Static), v, 60);
+    assert_eq!(p, Vec2::default());
+    assert_eq!(v, Vec2::default());
+    let (p, _) = fly(RigidBodyBuilder::new(), v, 60);
+    assert!(p.y < 0.0);
+}
+
+#[test]
+#[should_panic]
+fn dynamic_body_needs_mass() {
+    RigidBodyBuilder::new().set_mass(0.0).build();
+}
+
+#[test]
+fn projectile_arc_is_independent_of_step_size() {
+    // 50 px/s sideways and 150 px/s up, minus half of gravity after a second.
+    let expected = Vec2::from((50.0, 150.0 - 594.0 / 2.0));
+    for hz in [20, 30, 60, 144, 240].iter() {
+        let mut config = PhysicsConfig::default();
+        config.set_rate(*hz);
+        let mut body = RigidBodyBuilder::new().set_mass(2.0).build();
+        body.apply_impulse(Vec2::from((100.0, 300.0)));
+        let (p, _) = simulate(config, body, *hz as usize, Vec2::default());
+        assert!((p - expected).module() < 1e-3, "{} Hz: {:?}", hz, p);
+    }
+}
+
+#[test]
+fn forces_last_one_step_and_impulses_are_instant() {
+    let mut body = RigidBodyBuilder::new()
+        .set_mass(2.0)
+        .gravity_scale(0.0)
+        .build();
+    body.apply_impulse(Vec2::from((10.0, 0.0)));
+    assert_eq!(body.velocity, Vec2::from((5.0, 0.0)));
+
+    // Pushing for a second adds `force / mass` to the velocity, whatever the rate.
+    for hz in [30, 60, 144].iter() {
+        let mut config = PhysicsConfig::default();
+        config.set_rate(*hz);
+        let body = RigidBodyBuilder::new()
+            .set_mass(2.0)
+            .gravity_scale(0.0)
+            .build();
+        let (_, v) = simulate(config, body, *hz as usize, Vec2::from((10.0, 0.0)));
+        assert!((v.x - 5.0).abs() < 1e-6, "{} Hz: {:?}", hz, v);
+    }
+}
+
+#[test]
+fn off_centre_impulse_spins_the_body() {
+    let mut body = RigidBodyBuilder::new().inertia(2.0).build();
+    body.apply_impulse_at_point(Vec2::from((0.0, 1.0)), Vec2::from((1.0, 0.0)));
+    assert_eq!(body.velocity, Vec2::from((0.0, 1.0)));
+    assert_eq!(body.angular_velocity, 0.5);
+
+    let mut body = RigidBodyBuilder::new().build();
+    body.apply_impulse_at_point(Vec2::from((0.0, 1.0)), Vec2::from((1.0, 0.0)));
+    assert_eq!(body.angular_velocity, 0.0);
+}
+
+#[test]
+fn rate_is_at_least_one_hertz() {
+    let mut config = PhysicsConfig::default();
+    config.set_rate(0);
+    assert_eq!(config.time_step, Duration::from_secs(1));
+}
@@ -0,0 +1,309 @@
+//! Questions about the colliders of the world, such as "what is below my feet".
+//!
+//! Every query takes a `QueryData`, which systems can borrow as part of their `SystemData`, or
+//! `World::system_data` can build. Only entities in one of the layers of `mask` are considered,
+//! entities without a `CollisionFilter` being in the default layer.
+//!
+//! Colliders are looked up in the `BroadPhase`, where they are as of the last physics step.
+
+use crate::collider::{time_of_impact, Aabb, BroadPhase, Collider, ColliderType, CollisionFilter};
+use crate::transform::{Transform, TransformBuilder};
+use math::Vec2;
+use specs::prelude::*;
+
+pub type QueryData<'a> = (
+    Entities<'a>,
+    Read<'a, BroadPhase>,
+    ReadStorage<'a, Collider>,
+    ReadStorage<'a, Transform>,
+    ReadStorage<'a, CollisionFilter>,
+);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    pub entity: Entity,
+    pub point: Vec2<f64>,
+    /// Surface normal of `entity` at `point`.
+    pub normal: Vec2<f64>,
+    pub distance: f64,
+}
+
+/// Colliders in `mask` whose bounds overlap `aabb`.
+fn candidates<'d>(
+    (entities, broad_phase, colliders, transforms, filters): &'d QueryData,
+    aabb: &Aabb,
+    mask: u32,
+) -> impl Iterator<Item = (Entity, &'d Collider, &'d Transform)> + 'd {
+    broad_phase
+        .candidates(aabb)
+        .into_iter()
+        .filter(move |e| entities.is_alive(*e))
+        .filter(move |e| filters.get(*e).cloned().unwrap_or_default().membership & mask != 0)
+        .filter_map(move |e| Some((e, colliders.get(e)?, transforms.get(e)?)))
+}
+
+fn closest(best: Option<Hit>, hit: Hit) -> Option<Hit> {
+    match best {
+        Some(b) if b.distance <= hit.distance => Some(b),
+        _ => Some(hit),
+    }
+}
+
+/// First collider hit by a ray from `origin` along `direction`, within `max_distance`.
+pub fn raycast(
+    data: &QueryData,
+    origin: Vec2<f64>,
+    direction: Vec2<f64>,
+    max_distance: f64,
+    mask: u32,
+) -> Option<Hit> {
+    let direction = direction.normalize();
+    // Rays cast along an axis may go infinitely far without leaving it.
+    let reach = |o: f64, d: f64| if d == 0.0 { o } else { o + d * max_distance };
+    let end = Vec2::from((reach(origin.x, direction.x), reach(origin.y, direction.y)));
+    let bounds = Aabb {
+        min: Vec2::from((origin.x.min(end.x), origin.y.min(end.y))),
+        max: Vec2::from((origin.x.max(end.x), origin.y.max(end.y))),
+    };
+    candidates(data, &bounds, mask)
+        .filter_map(|(e, c, t)| {
+            let (distance, normal) = c.shape(t)?.raycast(origin, direction)?;
+            Some(Hit {
+                entity: e,
+                point: origin + direction * distance,
+                normal,
+                distance,
+            })
+        })
+        .filter(|hit| hit.distance <= max_distance)
+        .fold(None, closest)
+}
+
+/// First collider hit by `shape`, placed at `transform`, moving along `direction` for up to
+/// `max_distance`. Colliders it already overlaps are hit at distance 0.
+pub fn shape_cast(
+    data: &QueryData,
+    shape: &ColliderType,
+    transform: &Transform,
+    direction: Vec2<f64>,
+    max_distance: f64,
+    mask: u32,
+) -> Option<Hit> {
+    let direction = direction.normalize();
+    let at = |distance| {
+        let mut t = transform.clone();
+        t.position += direction * distance;
+        t
+    };
+    let from = shape.aabb(transform);
+    // Shapes cast along an axis may go infinitely far without leaving it.
+    let reach = |min: f64, max: f64, d: f64| {
+        if d == 0.0 {
+            (min, max)
+        } else {
+            (
+                min.min(min + d * max_distance),
+                max.max(max + d * max_distance),
+            )
+        }
+    };
+    let (min_x, max_x) = reach(from.min.x, from.max.x, direction.x);
+    let (min_y, max_y) = reach(from.min.y, from.max.y, direction.y);
+    let swept = Aabb {
+        min: Vec2::from((min_x, min_y)),
+        max: Vec2::from((max_x, max_y)),
+    };
+
+    candidates(data, &swept, mask)
+        .filter_map(|(e, c, t)| {
+            // Past the far side of the candidate bounds, it can't be hit anymore.
+            let other = c.aabb(t);
+            let distance = [
+                (
+                    from.min.x,
+                    from.max.x,
+                    other.min.x,
+                    other.max.x,
+                    direction.x,
+                ),
+                (
+                    from.min.y,
+                    from.max.y,
+                    other.min.y,
+                    other.max.y,
+                    direction.y,
+                ),
+            ]
+            .iter()
+            .filter(|(.., d)| *d != 0.0)
+            .map(|(min, max, other_min, other_max, d)| {
+                if *d > 0.0 {
+                    (other_max - min) / d
+                } else {
+                    (other_min - max) / d
+                }
+            })
+            .fold(max_distance, f64::min)
+            .max(0.0);
+            let hi = time_of_impact(shape, transform, direction * distance, c, t)? * distance;
+
+            let moved = at(hi);
+            let contact = shape.collide_with(c, &moved, t)?;
+            let points = shape.contact_points(c, &moved, t, &contact);
+            let point = if points.is_empty() {
+                moved.position
+            } else {
+                points.iter().fold(Vec2::default(), |a, p| a + *p) / points.len() as f64
+            };
+            Some(Hit {
+                entity: e,
+                point,
+                normal: contact.normal,
+                distance: hi,
+            })
+        })
+        .fold(None, closest)
+}
+
+/// Colliders containing `point`.
+pub fn overlap_point(data: &QueryData, point: Vec2<f64>, mask: u32) -> Vec<Entity> {
+    let t = TransformBuilder::new().position(point).build();
+    overlap_shape(data, &ColliderType::Circle(0.0), &t, mask)
+}
+
+/// Colliders overlapping `shape` placed at `transform`.
+pub fn overlap_shape(
+    data: &QueryData,
+    shape: &ColliderType,
+    transform: &Transform,
+    mask: u32,
+) -> Vec<Entity> {
+    let bounds = shape.aabb(transform);
+    candidates(data, &bounds, mask)
+        .filter(|(_, c, t)| shape.collide_with(c, transform, t).is_some())
+        .map(|(e, _, _)| e)
+        .collect()
+}
+
+#[cfg(test)]
+fn level() -> crate::testing::Harness {
+    use crate::collider::ColliderBuilder;
+
+    let mut h = crate::testing::Harness::new();
+    // Ground, top at y = 0, and a wall in another layer, left side at x = 50.
+    for (x, y, w, height, layer) in
+        [(0.0, -20.0, 200.0, 40.0, 1), (60.0, 50.0, 20.0, 100.0, 2)].iter()
+    {
+        let e = h.add_collider(
+            Vec2::from((*x, *y)),
+            ColliderBuilder::new()
+                .collider_type(ColliderType::Rect(*w, *height))
+                .build(),
+        );
+        h.world()
+            .write_storage::<CollisionFilter>()
+            .insert(e, CollisionFilter::new(*layer, !0))
+            .unwrap();
+    }
+    h.idle(1);
+    h
+}
+
+#[test]
+fn raycast_finds_closest_surface() {
+    let h = level();
+    let data: QueryData = h.game.world.system_data();
+
+    let hit = raycast(
+        &data,
+        Vec2::from((0.0, 30.0)),
+        Vec2::from((0.0, -2.0)),
+        100.0,
+        !0,
+    )
+    .unwrap();
+    assert_eq!(hit.point, Vec2::from((0.0, 0.0)));
+    assert_eq!(hit.normal, Vec2::from((0.0, 1.0)));
+    assert_eq!(hit.distance, 30.0);
+    assert!(raycast(
+        &data,
+        Vec2::from((0.0, 30.0)),
+        Vec2::from((0.0, -1.0)),
+        20.0,
+        !0
+    )
+    .is_none());
+
+    let right = Vec2::from((1.0, 0.0));
+    let hit = raycast(&data, Vec2::from((0.0, 30.0)), right, 100.0, !0).unwrap();
+    assert_eq!((hit.distance, hit.normal), (50.0, Vec2::from((-1.0, 0.0))));
+    assert!(raycast(&data, Vec2::from((0.0, 30.0)), right, 100.0, 1).is_none());
+}
+
+#[test]
+fn shape_cast_stops_on_contact() {
+    let h = level();
+    let data: QueryData = h.game.world.system_data();
+
+    let t = TransformBuilder::new()
+        .position(Vec2::from((0.0, 30.0)))
+        .build();
+    let hit = shape_cast(
+        &data,
+        &ColliderType::Circle(5.0),
+        &t,
+        Vec2::from((0.0, -1.0)),
+        100.0,
+        !0,
+    )
+    .unwrap();
+    assert!((hit.distance - 25.0).abs() < 1e-3, "{:?}", hit);
+    assert!(
+        (hit.point - Vec2::from((0.0, 0.0))).module() < 1e-3,
+        "{:?}",
+        hit
+    );
+    assert!(
+        (hit.normal - Vec2::from((0.0, 1.0))).module() < 1e-3,
+        "{:?}",
+        hit
+    );
+}
+
+#[test]
+fn shape_cast_goes_infinitely_far() {
+    let h = level();
+    let data: QueryData = h.game.world.system_data();
+
+    let cast = |x, y, direction: (f64, f64)| {
+        let t = TransformBuilder::new().position(Vec2::from((x, y))).build();
+        shape_cast(
+            &data,
+            &ColliderType::Circle(5.0),
+            &t,
+            Vec2::from(direction),
+            f64::INFINITY,
+            !0,
+        )
+    };
+    let hit = cast(0.0, 30.0, (1.0, 0.0)).unwrap();
+    assert!((hit.distance - 45.0).abs() < 1e-3, "{:?}", hit);
+    // The wall bounds are in the way of the swept bounds, not of the circle.
+    assert_eq!(cast(30.0, 104.0, (1.0, 1.0)), None);
+}
+
+#[test]
+fn overlaps_respect_mask() {
+    let h = level();
+    let data: QueryData = h.game.world.system_data();
+
+    assert_eq!(overlap_point(&data, Vec2::from((0.0, -10.0)), !0).len(), 1);
+    assert!(overlap_point(&data, Vec2::from((0.0, 10.0)), !0).is_empty());
+
+    let t = TransformBuilder::new()
+        .position(Vec2::from((50.0, 0.0)))
+        .build();
+    let probe = ColliderType::Rect(10.0, 10.0);
+    assert_eq!(overlap_shape(&data, &probe, &t, !0).len(), 2);
+    assert_eq!(overlap_shape(&data, &probe, &t, 2).len(), 1);
+}
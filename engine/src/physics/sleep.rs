@@ -0,0 +1,216 @@
+use super::{BodyType, Joint, PhysicsConfig, RigidBody};
+use crate::collider::{BroadPhase, Collisions};
+use specs::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Dynamic bodies touching each other or linked by a joint form an island. Once every body of
+/// an island stayed under `PhysicsConfig::sleep_velocity` for `PhysicsConfig::sleep_time`, the
+/// whole island falls asleep, and it wakes up together as soon as one of them moves again.
+pub struct SleepSystem;
+impl<'a> System<'a> for SleepSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, PhysicsConfig>,
+        Read<'a, BroadPhase>,
+        ReadStorage<'a, Collisions>,
+        ReadStorage<'a, Joint>,
+        WriteStorage<'a, RigidBody>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, config, broad_phase, collisions, joints, mut rigid_bodies): Self::SystemData,
+    ) {
+        let dynamic = |e| {
+            rigid_bodies
+                .get(e)
+                .map(|r: &RigidBody| r.body_type == BodyType::Dynamic)
+                .unwrap_or(false)
+        };
+
+        let mut islands = Islands::default();
+        let mut disturbed = HashSet::new();
+        for (e, c) in (&entities, &collisions).join() {
+            if !dynamic(e) {
+                continue;
+            }
+            for hit in c.iter().filter(|hit| !hit.sensor) {
+                if dynamic(hit.with) {
+                    islands.join(e, hit.with);
+                } else if broad_phase.has_moved(hit.with)
+                    || rigid_bodies
+                        .get(hit.with)
+                        .map(|r| r.velocity != Default::default())
+                        .unwrap_or(false)
+                {
+                    // Standing on something moving.
+                    disturbed.insert(e);
+                }
+            }
+            if c.exited().any(|hit| !hit.sensor) {
+                disturbed.insert(e);
+            }
+        }
+        for joint in (&joints).join() {
+            if dynamic(joint.a) && dynamic(joint.b) {
+                islands.join(joint.a, joint.b);
+            }
+        }
+
+        for (e, r) in (&entities, &mut rigid_bodies).join() {
+            if r.body_type != BodyType::Dynamic {
+                continue;
+            }
+            if disturbed.contains(&e) {
+                r.wake();
+            } else if r.can_sleep
+                && r.velocity.module() < config.sleep_velocity
+                && r.angular_velocity.abs() < config.sleep_angular_velocity
+            {
+                r.idle += config.time_step;
+            } else {
+                r.idle = Default::default();
+            }
+        }
+
+        let mut ready = HashMap::new();
+        for (e, r) in (&entities, &rigid_bodies).join() {
+            if r.body_type == BodyType::Dynamic {
+                *ready.entry(islands.root(e)).or_insert(true) &= r.idle >= config.sleep_time;
+            }
+        }
+        for (e, r) in (&entities, &mut rigid_bodies).join() {
+            match ready.get(&islands.root(e)) {
+                Some(true) if !r.sleeping => r.sleep(),
+                Some(false) if r.sleeping => r.wake(),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Union-find over entities.
+#[derive(Default)]
+struct Islands(HashMap<Entity, Entity>);
+
+impl Islands {
+    fn root(&mut self, e: Entity) -> Entity {
+        let parent = *self.0.get(&e).unwrap_or(&e);
+        if parent == e {
+            return e;
+        }
+        let root = self.root(parent);
+        self.0.insert(e, root);
+        root
+    }
+
+    fn join(&mut self, a: Entity, b: Entity) {
+        let (a, b) = (self.root(a), self.root(b));
+        if a != b {
+            self.0.insert(a, b);
+        }
+    }
+}
+
+#[cfg(test)]
+fn place(
+    h: &mut crate::testing::Harness,
+    position: (f64, f64),
+    size: (f64, f64),
+    body: bool,
+) -> Entity {
+    use crate::collider::{ColliderBuilder, ColliderType};
+
+    let collider = ColliderBuilder::new()
+        .collider_type(ColliderType::Rect(size.0, size.1))
+        .build();
+    if body {
+        h.add_body(position.into(), RigidBody::default(), collider)
+    } else {
+        h.add_collider(position.into(), collider)
+    }
+}
+
+#[cfg(test)]
+fn asleep(h: &crate::testing::Harness, e: Entity) -> bool {
+    h.game
+        .world
+        .read_storage::<RigidBody>()
+        .get(e)
+        .unwrap()
+        .is_sleeping()
+}
+
+#[test]
+fn resting_body_falls_asleep_until_pushed() {
+    let mut h = crate::testing::Harness::new();
+    place(&mut h, (0.0, 0.0), (100.0, 10.0), false);
+    let body = place(&mut h, (0.0, 10.0), (10.0, 10.0), true);
+    h.idle(60);
+    assert!(asleep(&h, body));
+    let rest = h.snapshot(body).position;
+    h.idle(10);
+    assert_eq!(h.snapshot(body).position, rest);
+
+    h.world()
+        .write_storage::<RigidBody>()
+        .get_mut(body)
+        .unwrap()
+        .apply_impulse(math::Vec2::from((0.0, 300.0)));
+    h.idle(1);
+    assert!(!asleep(&h, body));
+    assert!(h.snapshot(body).position.y > rest.y);
+}
+
+#[test]
+fn island_wakes_up_together() {
+    use super::{JointBuilder, JointType, RigidBodyBuilder};
+
+    let mut h = crate::testing::Harness::new();
+    place(&mut h, (0.0, 0.0), (100.0, 10.0), false);
+    let a = place(&mut h, (-10.0, 10.0), (10.0, 10.0), true);
+    let b = place(&mut h, (10.0, 10.0), (10.0, 10.0), true);
+    h.world()
+        .create_entity()
+        .with(JointBuilder::new(a, b, JointType::Distance(20.0)).build())
+        .build();
+    let awake = h
+        .world()
+        .create_entity()
+        .with(RigidBodyBuilder::new().never_sleep().build())
+        .build();
+    h.idle(60);
+    assert!(asleep(&h, a) && asleep(&h, b));
+    assert!(!asleep(&h, awake));
+
+    // Only `b` is hit, `a` is woken through the joint.
+    place(&mut h, (10.0, 30.0), (10.0, 10.0), true);
+    let mut woken = false;
+    for _ in 0..30 {
+        h.idle(1);
+        woken |= !asleep(&h, a);
+    }
+    assert!(woken);
+}
+
+#[test]
+fn sleeper_falls_when_ground_moves() {
+    use crate::transform::Transform;
+
+    let mut h = crate::testing::Harness::new();
+    let ground = place(&mut h, (0.0, 0.0), (100.0, 10.0), false);
+    let body = place(&mut h, (0.0, 10.0), (10.0, 10.0), true);
+    h.idle(60);
+    assert!(asleep(&h, body));
+    let rest = h.snapshot(body).position;
+
+    h.world()
+        .write_storage::<Transform>()
+        .get_mut(ground)
+        .unwrap()
+        .position = math::Vec2::from((0.0, -50.0));
+    h.idle(1);
+    assert!(!asleep(&h, body));
+    h.idle(30);
+    assert!(h.snapshot(body).position.y < rest.y - 40.0);
+}
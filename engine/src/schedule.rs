@@ -0,0 +1,204 @@
+use super::{camera, collider, event, physics, render, time, transform, Game};
+use specs::prelude::*;
+use specs::shred::RunNow;
+use specs::shrev::EventChannel;
+
+/// Where a system runs within a `Game::tick`.
+///
+/// `Input` and `Render` run once per tick, every other stage runs once per fixed step, in
+/// declaration order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    Input,
+    PrePhysics,
+    Physics,
+    Collision,
+    PostPhysics,
+    Animation,
+    Render,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 7] = [
+        Stage::Input,
+        Stage::PrePhysics,
+        Stage::Physics,
+        Stage::Collision,
+        Stage::PostPhysics,
+        Stage::Animation,
+        Stage::Render,
+    ];
+
+    pub fn is_fixed(self) -> bool {
+        !matches!(self, Stage::Input | Stage::Render)
+    }
+
+    fn index(self) -> usize {
+        Stage::ALL.iter().position(|s| *s == self).unwrap()
+    }
+}
+
+pub struct GameBuilder {
+    world: World,
+    stages: Vec<DispatcherBuilder<'static, 'static>>,
+}
+
+impl Default for GameBuilder {
+    fn default() -> Self {
+        GameBuilder::new()
+    }
+}
+
+impl GameBuilder {
+    pub fn new() -> Self {
+        let mut world = World::new();
+        world.insert(event::KeyPress::default());
+        world.insert(camera::Camera::default());
+        world.insert(time::Time::default());
+        world.insert(time::Interpolation::default());
+        world.insert(physics::PhysicsConfig::default());
+        world.insert(collider::CollisionLayers::default());
+        world.insert(collider::BroadPhase::default());
+        world.insert(EventChannel::<collider::CollisionStarted>::new());
+        world.insert(EventChannel::<collider::CollisionEnded>::new());
+        world.register::<collider::Collider>();
+        world.register::<collider::Collisions>();
+        world.register::<collider::CollisionFilter>();
+        world.register::<physics::RigidBody>();
+        world.register::<physics::Joint>();
+        world.register::<physics::Effector>();
+        world.register::<physics::CharacterController>();
+        world.register::<transform::Transform>();
+        world.register::<render::sprite::Sprite>();
+
+        GameBuilder {
+            world,
+            stages: Stage::ALL
+                .iter()
+                .map(|_| DispatcherBuilder::new())
+                .collect(),
+        }
+        .with(Stage::Physics, physics::CharacterSystem, "character", &[])
+        .with(Stage::Physics, physics::EffectorSystem, "effectors", &[])
+        .with(
+            Stage::Physics,
+            physics::PhysicsSystem,
+            "physics",
+            &["character", "effectors"],
+        )
+        .with(Stage::Physics, physics::JointSystem, "joints", &["physics"])
+        .with(Stage::Physics, collider::CarrySystem, "carry", &["joints"])
+        .with(
+            Stage::Physics,
+            collider::BroadPhaseSystem::default(),
+            "broad_phase",
+            &["carry"],
+        )
+        .with(Stage::Physics, collider::CcdSystem, "ccd", &["broad_phase"])
+        .with(
+            Stage::Collision,
+            collider::CollisionSystem,
+            "collision",
+            &[],
+        )
+        .with(
+            Stage::Collision,
+            collider::RepultionSystem,
+            "repulsion",
+            &["collision"],
+        )
+        .with(
+            Stage::Collision,
+            physics::SleepSystem,
+            "sleep",
+            &["repulsion"],
+        )
+        .with(
+            Stage::Animation,
+            render::sprite::SpriteUpdaterSystem,
+            "sprite",
+            &[],
+        )
+    }
+
+    /// Adds `system` to `stage`, running after the systems of the same stage named in `dep`.
+    pub fn with<S>(mut self, stage: Stage, system: S, name: &str, dep: &[&str]) -> Self
+    where
+        S: for<'a> System<'a> + Send + 'static,
+    {
+        self.stages[stage.index()].add(system, name, dep);
+        self
+    }
+
+    /// Adds a system that must run on the main thread, after every other system of `stage`.
+    pub fn with_thread_local<S>(mut self, stage: Stage, system: S) -> Self
+    where
+        S: for<'a> RunNow<'a> + 'static,
+    {
+        self.stages[stage.index()].add_thread_local(system);
+        self
+    }
+
+    pub fn world(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    pub fn build(self) -> Game {
+        let GameBuilder { mut world, stages } = self;
+
+        let mut snapshot = transform::TransformSnapshotSystem;
+        RunNow::setup(&mut snapshot, &mut world);
+
+        let stages = Stage::ALL
+            .iter()
+            .cloned()
+            .zip(stages.into_iter().map(|b| {
+                let mut dispatcher = b.build();
+                dispatcher.setup(&mut world);
+                dispatcher
+            }))
+            .collect();
+
+        Game {
+            world,
+            snapshot,
+            stages,
+        }
+    }
+}
+
+#[test]
+fn fixed_stages_run_once_per_step() {
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct Counter(u32, u32);
+
+    struct CountStep;
+    impl<'a> System<'a> for CountStep {
+        type SystemData = Write<'a, Counter>;
+
+        fn run(&mut self, mut c: Self::SystemData) {
+            c.0 += 1;
+        }
+    }
+
+    struct CountRender;
+    impl<'a> System<'a> for CountRender {
+        type SystemData = Write<'a, Counter>;
+
+        fn run(&mut self, mut c: Self::SystemData) {
+            c.1 += 1;
+        }
+    }
+
+    let mut game = GameBuilder::new()
+        .with(Stage::PrePhysics, CountStep, "count_step", &[])
+        .with_thread_local(Stage::Render, CountRender)
+        .build();
+    game.tick(Duration::from_millis(34));
+
+    let c = game.world.read_resource::<Counter>();
+    assert_eq!(c.0, 2);
+    assert_eq!(c.1, 1);
+}
@@ -0,0 +1,183 @@
+//! Headless harness driving a `Game` frame by frame with scripted key presses.
+
+use crate::collider::{Collider, Collision, Collisions};
+use crate::event::KeyPress;
+use crate::physics::{PhysicsConfig, RigidBody};
+use crate::transform::{Transform, TransformBuilder};
+use crate::Game;
+use math::Vec2;
+use specs::prelude::*;
+use specs::shred::RunNow;
+
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub entity: Entity,
+    pub position: Vec2<f64>,
+    pub rotation: f64,
+    pub velocity: Option<Vec2<f64>>,
+    pub angular_velocity: Option<f64>,
+    pub collisions: Vec<Collision>,
+    pub has_hit_bottom: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Frame {
+    pub index: usize,
+    pub keys: Vec<String>,
+    pub snapshots: Vec<Snapshot>,
+}
+
+impl Frame {
+    pub fn of(&self, entity: Entity) -> Option<&Snapshot> {
+        self.snapshots.iter().find(|s| s.entity == entity)
+    }
+}
+
+pub struct Harness {
+    pub game: Game,
+    systems: Vec<Box<dyn for<'a> RunNow<'a>>>,
+    tracked: Vec<Entity>,
+    held: Vec<String>,
+    frames: Vec<Frame>,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Harness::new()
+    }
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        Harness::from_game(Game::new())
+    }
+
+    pub fn from_game(game: Game) -> Self {
+        Harness {
+            game,
+            systems: Vec::new(),
+            tracked: Vec::new(),
+            held: Vec::new(),
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn world(&mut self) -> &mut World {
+        &mut self.game.world
+    }
+
+    /// Registers a gameplay system run once per frame, before the engine step.
+    pub fn add_system<S>(&mut self, mut system: S)
+    where
+        S: for<'a> RunNow<'a> + 'static,
+    {
+        system.setup(&mut self.game.world);
+        self.systems.push(Box::new(system));
+    }
+
+    /// Adds level geometry at `position`.
+    pub fn add_collider(&mut self, position: Vec2<f64>, collider: Collider) -> Entity {
+        self.game
+            .world
+            .create_entity()
+            .with(TransformBuilder::new().position(position).build())
+            .with(collider)
+            .build()
+    }
+
+    /// Adds a body at `position`, tracked from the next frame on.
+    pub fn add_body(&mut self, position: Vec2<f64>, body: RigidBody, collider: Collider) -> Entity {
+        let e = self.add_collider(position, collider);
+        self.game
+            .world
+            .write_storage::<RigidBody>()
+            .insert(e, body)
+            .unwrap();
+        self.game
+            .world
+            .write_storage::<Collisions>()
+            .insert(e, Collisions::default())
+            .unwrap();
+        self.track(e);
+        e
+    }
+
+    /// Records a snapshot of `entity` after every frame.
+    pub fn track(&mut self, entity: Entity) {
+        self.tracked.push(entity);
+    }
+
+    /// Runs one frame with exactly `keys` held down.
+    pub fn frame(&mut self, keys: &[&str]) -> &Frame {
+        {
+            let mut kp = self.game.world.write_resource::<KeyPress>();
+            for k in &self.held {
+                kp.update_from_str(k, false);
+            }
+            for k in keys {
+                kp.update_from_str(k, true);
+            }
+        }
+        self.held = keys.iter().map(|k| k.to_string()).collect();
+
+        for s in &mut self.systems {
+            s.run_now(&self.game.world);
+        }
+        let step = self.game.world.read_resource::<PhysicsConfig>().time_step;
+        self.game.tick(step);
+
+        let frame = Frame {
+            index: self.frames.len(),
+            keys: self.held.clone(),
+            snapshots: self.tracked.iter().map(|e| self.snapshot(*e)).collect(),
+        };
+        self.frames.push(frame);
+        self.frames.last().unwrap()
+    }
+
+    /// Runs one frame per entry of `script`.
+    pub fn run(&mut self, script: &[&[&str]]) {
+        for keys in script {
+            self.frame(keys);
+        }
+    }
+
+    /// Runs `n` frames holding `keys`.
+    pub fn hold(&mut self, keys: &[&str], n: usize) {
+        for _ in 0..n {
+            self.frame(keys);
+        }
+    }
+
+    pub fn idle(&mut self, n: usize) {
+        self.hold(&[], n);
+    }
+
+    pub fn frames(&self) -> &Vec<Frame> {
+        &self.frames
+    }
+
+    pub fn last(&self) -> Option<&Frame> {
+        self.frames.last()
+    }
+
+    pub fn snapshot(&self, entity: Entity) -> Snapshot {
+        let transforms = self.game.world.read_storage::<Transform>();
+        let rigid_bodies = self.game.world.read_storage::<RigidBody>();
+        let collisions = self.game.world.read_storage::<Collisions>();
+
+        let c = collisions.get(entity);
+        let t = transforms.get(entity);
+        let r = rigid_bodies.get(entity);
+
+        Snapshot {
+            entity,
+            position: t.map(|t| t.position).unwrap_or_default(),
+            rotation: t.map(|t| t.rotation).unwrap_or_default(),
+            velocity: r.map(|r| r.velocity),
+            angular_velocity: r.map(|r| r.angular_velocity),
+            collisions: c.map(|c| c.to_vec()).unwrap_or_default(),
+            has_hit_bottom: c.map(|c| c.has_hit_bottom()).unwrap_or(false),
+        }
+    }
+}
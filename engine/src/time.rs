@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+pub struct Time {
+    step: Duration,
+    max_steps: u32,
+    accumulator: Duration,
+    steps: u32,
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Time {
+            step: Duration::from_nanos(1_000_000_000 / 60),
+            max_steps: 5,
+            accumulator: Duration::from_secs(0),
+            steps: 0,
+        }
+    }
+}
+
+impl Time {
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Set from `PhysicsConfig::time_step` at every `Game::tick`. A zero step is ignored, it
+    /// would never drain the accumulator.
+    pub(crate) fn set_step(&mut self, step: Duration) {
+        if step > Duration::from_secs(0) {
+            self.step = step;
+        }
+    }
+
+    pub fn max_steps(&self) -> u32 {
+        self.max_steps
+    }
+
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps;
+    }
+
+    /// Number of fixed steps run during the last `Game::tick`.
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.accumulator.as_secs_f64() / self.step.as_secs_f64()
+    }
+
+    pub(crate) fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed;
+
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            if steps == self.max_steps {
+                // Too far behind to catch up, drop the backlog instead of spiraling.
+                let rest = self.accumulator.as_nanos() % self.step.as_nanos();
+                self.accumulator = Duration::from_nanos(rest as u64);
+                break;
+            }
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        self.steps = steps;
+        steps
+    }
+}
+
+/// How far the renderer is between the previous and the current fixed step, in `[0, 1)`.
+#[derive(Default)]
+pub struct Interpolation(pub f64);
+
+#[test]
+fn accumulates_partial_frames() {
+    let mut time = Time::default();
+
+    assert_eq!(time.advance(Duration::from_millis(10)), 0);
+    assert_eq!(time.advance(Duration::from_millis(10)), 1);
+    assert!(time.alpha() > 0.19 && time.alpha() < 0.21);
+}
+
+#[test]
+fn high_refresh_rate_runs_same_number_of_steps() {
+    let mut t60 = Time::default();
+    let mut t144 = Time::default();
+
+    let steps_60: u32 = (0..60)
+        .map(|_| t60.advance(Duration::from_nanos(1_000_000_000 / 60)))
+        .sum();
+    let steps_144: u32 = (0..144)
+        .map(|_| t144.advance(Duration::from_nanos(1_000_000_000 / 144)))
+        .sum();
+
+    assert_eq!(steps_60, 60);
+    assert!(steps_144 == 59 || steps_144 == 60);
+}
+
+#[test]
+fn caps_catch_up_steps() {
+    let mut time = Time::default();
+    time.set_max_steps(3);
+
+    assert_eq!(time.advance(Duration::from_secs(1)), 3);
+    assert!(time.alpha() < 1.0);
+    assert_eq!(time.advance(Duration::from_secs(0)), 0);
+}
+
+#[test]
+fn ignores_zero_step() {
+    let mut time = Time::default();
+    time.set_step(Duration::from_secs(0));
+
+    assert_eq!(time.advance(Duration::from_millis(20)), 1);
+    assert!(time.alpha().is_finite());
+}
@@ -38,28 +38,36 @@ impl TransformBuilder {
             scale,
         } = self;
 
+        let position = match position {
+            Some(v) => v,
+            None => Vec2::default(),
+        };
+        let rotation = match rotation {
+            Some(v) => v,
+            None => 0.0,
+        };
+
         Transform {
-            position: match position {
-                Some(v) => v,
-                None => Vec2::default(),
-            },
-            rotation: match rotation {
-                Some(v) => v,
-                None => 0.0,
-            },
+            position,
+            rotation,
             scale: match scale {
                 Some(v) => v,
                 None => Vec2::from((1.0, 1.0)),
             },
+            previous_position: position,
+            previous_rotation: rotation,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Transform {
     pub position: Vec2<f64>,
+    /// Counter-clockwise, in radians.
     pub rotation: f64,
     pub scale: Vec2<f64>,
+    previous_position: Vec2<f64>,
+    previous_rotation: f64,
 }
 
 impl Default for Transform {
@@ -68,11 +76,30 @@ impl Default for Transform {
             position: Vec2::default(),
             rotation: 0.0,
             scale: Vec2::from((1.0, 1.0)),
+            previous_position: Vec2::default(),
+            previous_rotation: 0.0,
         }
     }
 }
 
 impl Transform {
+    pub fn previous_position(&self) -> Vec2<f64> {
+        self.previous_position
+    }
+
+    pub fn interpolated_position(&self, alpha: f64) -> Vec2<f64> {
+        self.previous_position + (self.position - self.previous_position) * alpha
+    }
+
+    pub fn interpolated_rotation(&self, alpha: f64) -> f64 {
+        self.previous_rotation + (self.rotation - self.previous_rotation) * alpha
+    }
+
+    pub(crate) fn save_previous(&mut self) {
+        self.previous_position = self.position;
+        self.previous_rotation = self.rotation;
+    }
+
     pub fn translate(&mut self, other: Vec2<f64>) {
         self.position += other;
     }
@@ -97,3 +124,15 @@ impl Transform {
 impl Component for Transform {
     type Storage = VecStorage<Self>;
 }
+
+pub struct TransformSnapshotSystem;
+
+impl<'a> System<'a> for TransformSnapshotSystem {
+    type SystemData = WriteStorage<'a, Transform>;
+
+    fn run(&mut self, mut transforms: Self::SystemData) {
+        for t in (&mut transforms).join() {
+            t.save_previous();
+        }
+    }
+}
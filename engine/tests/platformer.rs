@@ -0,0 +1,244 @@
+use engine::math::Vec2;
+use engine::prelude::*;
+use engine::testing::Harness;
+use engine::KeyPress;
+use std::time::Duration;
+
+const WORLD: u32 = 1 << 1;
+const PLAYER: u32 = 1 << 2;
+
+struct Controller;
+impl<'a> System<'a> for Controller {
+    type SystemData = (
+        Read<'a, KeyPress>,
+        ReadStorage<'a, Collider>,
+        WriteStorage<'a, Collisions>,
+        WriteStorage<'a, RigidBody>,
+    );
+
+    fn run(&mut self, (kp, colliders, mut collisions, mut rigid_bodies): Self::SystemData) {
+        for (c, r) in (&mut collisions, &mut rigid_bodies).join() {
+            r.velocity.x = 0.0;
+            if kp.KeyD() {
+                r.velocity.x = 180.0;
+            }
+            if kp.KeyA() {
+                r.velocity.x = -180.0;
+            }
+            if kp.KeyW() && c.has_hit_bottom() {
+                r.apply_impulse(Vec2::from((0.0, 3000.0)));
+            }
+            if kp.KeyS() {
+                let ledges: Vec<_> = c
+                    .standing_on()
+                    .filter(|e| colliders.get(*e).and_then(|c| c.one_way()).is_some())
+                    .collect();
+                for e in ledges {
+                    c.ignore(e, Duration::from_millis(200));
+                }
+            }
+        }
+    }
+}
+
+fn block(world: &mut World, x: f64, y: f64, w: f64, h: f64) -> Entity {
+    world
+        .create_entity()
+        .with(TransformBuilder::new().position(Vec2::from((x, y))).build())
+        .with(
+            ColliderBuilder::new()
+                .collider_type(ColliderType::Rect(w, h))
+                .build(),
+        )
+        .with(CollisionFilter::new(WORLD, WORLD))
+        .with(Collisions::default())
+        .build()
+}
+
+fn player(world: &mut World, x: f64, y: f64) -> Entity {
+    world
+        .create_entity()
+        .with(TransformBuilder::new().position(Vec2::from((x, y))).build())
+        .with(RigidBodyBuilder::new().set_mass(10.0).build())
+        .with(
+            ColliderBuilder::new()
+                .collider_type(ColliderType::Rect(14.0, 30.0))
+                .build(),
+        )
+        .with(Collisions::default())
+        .with(CollisionFilter::new(PLAYER, WORLD))
+        .build()
+}
+
+fn level() -> (Harness, Entity) {
+    let mut h = Harness::new();
+    assert_eq!(h.game.register_layer("world"), WORLD);
+    assert_eq!(h.game.register_layer("player"), PLAYER);
+    h.add_system(Controller);
+    block(h.world(), 0.0, -20.0, 400.0, 40.0);
+    block(h.world(), 100.0, 40.0, 40.0, 80.0);
+    let p = player(h.world(), 0.0, 40.0);
+    h.track(p);
+    (h, p)
+}
+
+#[test]
+fn lands_on_ground() {
+    let (mut h, p) = level();
+    h.idle(120);
+
+    let s = h.last().unwrap().of(p).unwrap();
+    assert!(s.has_hit_bottom);
+    assert!((s.position.y - 15.0).abs() < 1.0, "y = {}", s.position.y);
+    assert_eq!(s.velocity.unwrap().y, 0.0);
+}
+
+#[test]
+fn jumps_and_lands_again() {
+    let (mut h, p) = level();
+    h.idle(120);
+    let ground = h.last().unwrap().of(p).unwrap().position.y;
+
+    h.frame(&["KeyW"]);
+    h.idle(10);
+    let s = h.last().unwrap().of(p).unwrap();
+    assert!(!s.has_hit_bottom);
+    assert!(s.position.y > ground + 10.0);
+
+    h.idle(200);
+    let s = h.last().unwrap().of(p).unwrap();
+    assert!(s.has_hit_bottom);
+    assert!((s.position.y - ground).abs() < 1.0);
+}
+
+#[test]
+fn stops_against_wall() {
+    let (mut h, p) = level();
+    h.idle(120);
+    h.hold(&["KeyD"], 120);
+
+    let s = h.last().unwrap().of(p).unwrap();
+    assert!(
+        s.position.x <= 100.0 - 20.0 - 7.0 + 1.0,
+        "x = {}",
+        s.position.x
+    );
+    assert!(s.collisions.iter().any(|c| c.at.x < 0.0));
+    assert!(s.collisions.iter().any(|c| c.side == Side::Right));
+    assert!(s.has_hit_bottom);
+}
+
+#[test]
+fn walks_through_sensor() {
+    let (mut h, p) = level();
+    let coin = h
+        .world()
+        .create_entity()
+        .with(
+            TransformBuilder::new()
+                .position(Vec2::from((40.0, 15.0)))
+                .build(),
+        )
+        .with(
+            ColliderBuilder::new()
+                .collider_type(ColliderType::Rect(10.0, 10.0))
+                .sensor()
+                .build(),
+        )
+        .with(CollisionFilter::new(WORLD, PLAYER))
+        .build();
+    h.idle(120);
+    h.hold(&["KeyD"], 30);
+
+    assert!(h
+        .frames()
+        .iter()
+        .filter_map(|f| f.of(p))
+        .any(|s| s.collisions.iter().any(|c| c.with == coin && c.sensor)));
+    let s = h.last().unwrap().of(p).unwrap();
+    assert!(s.position.x > 60.0, "x = {}", s.position.x);
+}
+
+#[test]
+fn rides_moving_platform() {
+    let (mut h, _) = level();
+    let mut platform = RigidBodyBuilder::new()
+        .body_type(BodyType::Kinematic)
+        .build();
+    platform.velocity = Vec2::from((-60.0, 30.0));
+    let start = Vec2::from((-100.0, 100.0));
+    let platform = h
+        .world()
+        .create_entity()
+        .with(TransformBuilder::new().position(start).build())
+        .with(platform)
+        .with(
+            ColliderBuilder::new()
+                .collider_type(ColliderType::Rect(60.0, 10.0))
+                .build(),
+        )
+        .with(CollisionFilter::new(WORLD, WORLD))
+        .build();
+    let rider = player(h.world(), -100.0, 125.0);
+    h.track(rider);
+    h.track(platform);
+    let offset = |h: &Harness| {
+        let f = h.last().unwrap();
+        f.of(rider).unwrap().position - f.of(platform).unwrap().position
+    };
+    h.idle(30);
+    let landed = offset(&h);
+    h.idle(60);
+
+    let s = h.last().unwrap().of(rider).unwrap();
+    assert!(s.collisions.iter().any(|c| c.with == platform));
+    assert!(
+        (offset(&h) - landed).module() < 0.5,
+        "{:?} {:?}",
+        landed,
+        offset(&h)
+    );
+    assert!((landed.y - 20.0).abs() < 1.0, "{:?}", landed);
+}
+
+#[test]
+fn jumps_through_ledge_and_drops_down() {
+    let (mut h, p) = level();
+    let ledge = h
+        .world()
+        .create_entity()
+        .with(
+            TransformBuilder::new()
+                .position(Vec2::from((0.0, 60.0)))
+                .build(),
+        )
+        .with(
+            ColliderBuilder::new()
+                .collider_type(ColliderType::Rect(60.0, 10.0))
+                .one_way(Vec2::from((0.0, 1.0)))
+                .build(),
+        )
+        .with(CollisionFilter::new(WORLD, WORLD))
+        .build();
+    h.idle(120);
+    let ground = h.last().unwrap().of(p).unwrap().position.y;
+
+    h.frame(&["KeyW"]);
+    h.idle(120);
+    let s = h.last().unwrap().of(p).unwrap();
+    assert!(s.collisions.iter().any(|c| c.with == ledge));
+    assert!((s.position.y - 80.0).abs() < 1.0, "y = {}", s.position.y);
+
+    // Walking under the ledge never hits its bottom or sides.
+    h.frame(&["KeyS"]);
+    h.idle(120);
+    let s = h.last().unwrap().of(p).unwrap();
+    assert!((s.position.y - ground).abs() < 1.0, "y = {}", s.position.y);
+    assert!(h
+        .frames()
+        .iter()
+        .filter_map(|f| f.of(p))
+        .flat_map(|s| s.collisions.iter())
+        .filter(|c| c.with == ledge)
+        .all(|c| c.side == Side::Bottom));
+}
@@ -29,6 +29,31 @@ impl Vec2<f64> {
     pub fn module(&self) -> f64 {
         self.amplitude_squared().sqrt()
     }
+
+    pub fn dot(&self, other: Self) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// Z component of the 3D cross product.
+    pub fn cross(&self, other: Self) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Rotated by a quarter turn counter-clockwise.
+    pub fn perpendicular(self) -> Self {
+        Vec2::from((-self.y, self.x))
+    }
+
+    /// Rotated counter-clockwise by `angle` radians.
+    pub fn rotate(self, angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Vec2::from((self.x * cos - self.y * sin, self.x * sin + self.y * cos))
+    }
+
+    /// Component-wise product.
+    pub fn scale(self, other: Self) -> Self {
+        Vec2::from((self.x * other.x, self.y * other.y))
+    }
 }
 
 impl<T> From<(T, T)> for Vec2<T> {
@@ -1,7 +1,9 @@
 use crate::helper::document;
+use engine::collider::Shape;
 use engine::components::{Collider, Sprite, Transform};
+use engine::math::Vec2;
 use engine::specs::prelude::*;
-use engine::Camera;
+use engine::{Camera, Interpolation};
 use js_sys::*;
 use lazy_static::*;
 // use log::*;
@@ -45,25 +47,31 @@ impl<'a> System<'a> for DebugCollider {
     fn run(&mut self, (_camera, transforms, colliders): Self::SystemData) {
         let ctx: &Context = &CTX;
 
-        for (t, c) in (&transforms, &colliders).join() {
-            let canvas_center_x = unsafe { CANVAS_WIDTH } as f64 / 2.0;
-            let canvas_center_y = unsafe { CANVAS_HEIGHT } as f64 / 2.0;
-
-            let obj_center_x = t.position.x;
-            let obj_center_y = t.position.y;
+        let canvas_center_x = unsafe { CANVAS_WIDTH } as f64 / 2.0;
+        let canvas_center_y = unsafe { CANVAS_HEIGHT } as f64 / 2.0;
+        let to_canvas = |p: &Vec2<f64>| (canvas_center_x + p.x, canvas_center_y - p.y);
 
-            let pos_x = canvas_center_x + obj_center_x;
-            let pos_y = canvas_center_y - obj_center_y;
-
-            match **c {
-                engine::types::ColliderType::Circle(r) => {
-                    ctx.draw_circle(pos_x, pos_y, r).unwrap();
+        for (t, c) in (&transforms, &colliders).join() {
+            match c.shape(t) {
+                Some(Shape::Circle(center, r)) => {
+                    let (x, y) = to_canvas(&center);
+                    ctx.draw_circle(x, y, r).unwrap();
                 }
-                engine::types::ColliderType::Rect(w, h) => {
-                    ctx.draw_rect(pos_x - w / 2.0, pos_y - h / 2.0, w, h)
-                        .unwrap();
+                Some(Shape::Capsule(a, b, r)) => {
+                    ctx.draw_capsule(to_canvas(&a), to_canvas(&b), r).unwrap();
                 }
-                _ => {}
+                Some(Shape::Polygon(points)) => {
+                    let points: Vec<_> = points.iter().map(to_canvas).collect();
+                    ctx.draw_polyline(&points, true);
+                }
+                Some(Shape::Segment(a, b)) => {
+                    ctx.draw_polyline(&[to_canvas(&a), to_canvas(&b)], false);
+                }
+                Some(Shape::Chain(points)) => {
+                    let points: Vec<_> = points.iter().map(to_canvas).collect();
+                    ctx.draw_polyline(&points, false);
+                }
+                None => {}
             }
         }
     }
@@ -73,11 +81,12 @@ pub struct SysRender;
 impl<'a> System<'a> for SysRender {
     type SystemData = (
         Read<'a, Camera>,
+        Read<'a, Interpolation>,
         ReadStorage<'a, Transform>,
         ReadStorage<'a, Sprite>,
     );
 
-    fn run(&mut self, (_camera, transforms, sprites): Self::SystemData) {
+    fn run(&mut self, (_camera, interpolation, transforms, sprites): Self::SystemData) {
         let ctx: &Context = &CTX;
 
         let mut res = ImgToDraw::new(unsafe { CANVAS_WIDTH } as usize, unsafe { CANVAS_HEIGHT }
@@ -91,8 +100,9 @@ impl<'a> System<'a> for SysRender {
                 let canvas_center_x = unsafe { CANVAS_WIDTH } as i32 / 2;
                 let canvas_center_y = unsafe { CANVAS_HEIGHT } as i32 / 2;
 
-                let obj_center_x = t.position.x as i32;
-                let obj_center_y = t.position.y as i32;
+                let position = t.interpolated_position(interpolation.0);
+                let obj_center_x = position.x as i32;
+                let obj_center_y = position.y as i32;
 
                 let obj_scale_x = t.scale.x as i32;
 
@@ -154,13 +164,41 @@ impl Context {
         Ok(())
     }
 
-    pub fn draw_rect(&self, x: f64, y: f64, w: f64, h: f64) -> Result<(), JsValue> {
+    /// Pill around the segment from `a` to `b`.
+    pub fn draw_capsule(&self, a: (f64, f64), b: (f64, f64), r: f64) -> Result<(), JsValue> {
+        let angle = (b.1 - a.1).atan2(b.0 - a.0);
+        let quarter_turn = f64::consts::PI / 2.0;
         self.ctx.set_stroke_style(&JsValue::from("green"));
         self.ctx.begin_path();
-        self.ctx.rect(x, y, w, h);
+        self.ctx
+            .arc(b.0, b.1, r, angle - quarter_turn, angle + quarter_turn)?;
+        self.ctx.arc(
+            a.0,
+            a.1,
+            r,
+            angle + quarter_turn,
+            angle + 3.0 * quarter_turn,
+        )?;
+        self.ctx.close_path();
         self.ctx.stroke();
         Ok(())
     }
+
+    pub fn draw_polyline(&self, points: &[(f64, f64)], closed: bool) {
+        self.ctx.set_stroke_style(&JsValue::from("green"));
+        self.ctx.begin_path();
+        for (i, (x, y)) in points.iter().enumerate() {
+            if i == 0 {
+                self.ctx.move_to(*x, *y);
+            } else {
+                self.ctx.line_to(*x, *y);
+            }
+        }
+        if closed {
+            self.ctx.close_path();
+        }
+        self.ctx.stroke();
+    }
 }
 
 struct ImgToDraw(Vec<u8>, usize, usize);
@@ -6,6 +6,7 @@ use helper::{body, request_animation_frame};
 use js_sys::*;
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Duration;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::ImageData;
@@ -67,9 +68,8 @@ struct TestMove;
 impl<'a> System<'a> for TestMove {
     type SystemData = (
         Read<'a, engine::KeyPress>,
-        ReadStorage<'a, Collisions>,
         WriteStorage<'a, Transform>,
-        WriteStorage<'a, RigidBody>,
+        WriteStorage<'a, CharacterController>,
         WriteStorage<'a, Player>,
         WriteStorage<'a, Sprite>,
         ReadStorage<'a, Player1>,
@@ -77,77 +77,65 @@ impl<'a> System<'a> for TestMove {
 
     fn run(
         &mut self,
-        (kp, collisions, mut transforms, mut rigidbodies, mut players, mut sprites, p1): Self::SystemData,
+        (kp, mut transforms, mut controllers, mut players, mut sprites, p1): Self::SystemData,
     ) {
-        for (c, t, r, p, s, _) in (
-            &collisions,
+        for (t, ch, p, s, _) in (
             &mut transforms,
-            &mut rigidbodies,
+            &mut controllers,
             &mut players,
             &mut sprites,
             &p1,
         )
             .join()
         {
-            let c: &Collisions = c;
             let t: &mut Transform = t;
-            let r: &mut RigidBody = r;
+            let ch: &mut CharacterController = ch;
             let p: &mut Player = p;
             let s: &mut Sprite = s;
 
-            let speed = if !kp.ShiftLeft() {
-                3.0
-            } else {
-                r.velocity.x *= 0.8;
-                1.0
-            };
+            let speed = if !kp.ShiftLeft() { 1.0 } else { 1.0 / 3.0 };
+            ch.run = 0.0;
+            ch.jump = kp.KeyW();
             let mut new_player_state = p.state.clone();
 
-            r.velocity.x *= 0.9;
-
             match &mut p.state {
                 PlayerState::Idle | PlayerState::Walk => {
                     new_player_state = PlayerState::Idle;
-                    r.velocity.x *= 0.9;
                     if kp.KeyD() {
                         new_player_state = PlayerState::Walk;
                         // t.translate(engine::math::Vec2::from((speed, 0.0)));
-                        r.velocity.x = speed;
+                        ch.run = speed;
                         t.face_right();
                     }
                     if kp.KeyA() {
                         new_player_state = PlayerState::Walk;
                         // t.translate(engine::math::Vec2::from((-speed, 0.0)));
-                        r.velocity.x = -speed;
+                        ch.run = -speed;
                         t.face_left();
                     }
-                    if kp.KeyW() {
-                        new_player_state = PlayerState::Jump;
-                        r.impulse(engine::math::Vec2::from((0.0, 50.0)));
-                    }
                     if kp.KeyK() {
                         new_player_state = PlayerState::Attack(15);
                     }
 
-                    if !c.has_hit_bottom() {
+                    if !ch.on_ground() {
                         new_player_state = PlayerState::Jump;
                     }
                 }
                 PlayerState::Jump => {
                     if kp.KeyD() {
                         // t.translate(engine::math::Vec2::from((speed, 0.0)));
-                        r.velocity.x = speed;
+                        ch.run = speed;
                         t.face_right();
                     }
                     if kp.KeyA() {
                         // t.translate(engine::math::Vec2::from((-speed, 0.0)));
-                        r.velocity.x = -speed;
+                        ch.run = -speed;
                         t.face_left();
                     }
                     if kp.KeyK() {
                         new_player_state = PlayerState::Attack(15);
                     }
-                    if c.has_hit_bottom() {
+                    if ch.on_ground() {
                         new_player_state = PlayerState::Idle;
                     }
                 }
@@ -162,7 +150,7 @@ impl<'a> System<'a> for TestMove {
                         t.face_left();
                     }
                     if *remaning_time < 0 {
-                        if c.has_hit_bottom() {
+                        if ch.on_ground() {
                             new_player_state = PlayerState::Idle;
                         } else {
                             new_player_state = PlayerState::Jump;
@@ -182,9 +170,8 @@ struct TestMove2;
 impl<'a> System<'a> for TestMove2 {
     type SystemData = (
         Read<'a, engine::KeyPress>,
-        ReadStorage<'a, Collisions>,
         WriteStorage<'a, Transform>,
-        WriteStorage<'a, RigidBody>,
+        WriteStorage<'a, CharacterController>,
         WriteStorage<'a, Player>,
         WriteStorage<'a, Sprite>,
         ReadStorage<'a, Player2>,
@@ -192,71 +179,59 @@ impl<'a> System<'a> for TestMove2 {
 
     fn run(
         &mut self,
-        (kp, collisions, mut transforms, mut rigidbodies, mut players, mut sprites, p2): Self::SystemData,
+        (kp, mut transforms, mut controllers, mut players, mut sprites, p2): Self::SystemData,
     ) {
-        for (c, t, r, p, s, _) in (
-            &collisions,
+        for (t, ch, p, s, _) in (
             &mut transforms,
-            &mut rigidbodies,
+            &mut controllers,
             &mut players,
             &mut sprites,
             &p2,
         )
             .join()
         {
-            let c: &Collisions = c;
             let t: &mut Transform = t;
-            let r: &mut RigidBody = r;
+            let ch: &mut CharacterController = ch;
             let p: &mut Player = p;
             let s: &mut Sprite = s;
 
-            let speed = if !kp.ShiftLeft() {
-                3.0
-            } else {
-                r.velocity.x *= 0.8;
-                1.0
-            };
+            let speed = if !kp.ShiftLeft() { 1.0 } else { 1.0 / 3.0 };
+            ch.run = 0.0;
+            ch.jump = kp.ArrowUp();
             let mut new_player_state = p.state.clone();
 
-            r.velocity.x *= 0.9;
-
             match &mut p.state {
                 PlayerState::Idle | PlayerState::Walk => {
                     new_player_state = PlayerState::Idle;
-                    r.velocity.x *= 0.9;
                     if kp.ArrowRight() {
                         new_player_state = PlayerState::Walk;
                         // t.translate(engine::math::Vec2::from((speed, 0.0)));
-                        r.velocity.x = speed;
+                        ch.run = speed;
                         t.face_right();
                     }
                     if kp.ArrowLeft() {
                         new_player_state = PlayerState::Walk;
                         // t.translate(engine::math::Vec2::from((-speed, 0.0)));
-                        r.velocity.x = -speed;
+                        ch.run = -speed;
                         t.face_left();
                     }
-                    if kp.ArrowUp() {
-                        new_player_state = PlayerState::Jump;
-                        r.impulse(engine::math::Vec2::from((0.0, 50.0)));
-                    }
 
-                    if !c.has_hit_bottom() {
+                    if !ch.on_ground() {
                         new_player_state = PlayerState::Jump;
                     }
                 }
                 PlayerState::Jump => {
                     if kp.ArrowRight() {
                         // t.translate(engine::math::Vec2::from((speed, 0.0)));
-                        r.velocity.x = speed;
+                        ch.run = speed;
                         t.face_right();
                     }
                     if kp.ArrowLeft() {
                         // t.translate(engine::math::Vec2::from((-speed, 0.0)));
-                        r.velocity.x = -speed;
+                        ch.run = -speed;
                         t.face_left();
                     }
-                    if c.has_hit_bottom() {
+                    if ch.on_ground() {
                         new_player_state = PlayerState::Idle;
                     }
                 }
@@ -271,7 +246,7 @@ impl<'a> System<'a> for TestMove2 {
                         t.face_left();
                     }
                     if *remaning_time < 0 {
-                        if c.has_hit_bottom() {
+                        if ch.on_ground() {
                             new_player_state = PlayerState::Idle;
                         } else {
                             new_player_state = PlayerState::Jump;
@@ -288,6 +263,8 @@ impl<'a> System<'a> for TestMove2 {
     }
 }
 
+const DEBUG_COLLIDERS: bool = false;
+
 #[wasm_bindgen]
 pub fn resize() -> Result<(), JsValue> {
     draw::resize()
@@ -300,7 +277,16 @@ pub fn start(player_image: ImageData) -> Result<(), JsValue> {
         player_image.width() as usize,
         player_image.height() as usize,
     );
-    let mut game = engine::Game::new();
+    let mut builder = GameBuilder::new()
+        .with(Stage::PrePhysics, TestMove, "player1_move", &[])
+        .with(Stage::PrePhysics, TestMove2, "player2_move", &[])
+        .with_thread_local(Stage::Render, draw::SysRender);
+    if DEBUG_COLLIDERS {
+        builder = builder.with_thread_local(Stage::Render, draw::DebugCollider);
+    }
+    let mut game = builder.build();
+    game.register_layer("world");
+    game.register_layer("player");
     game.world.register::<Player>();
     game.world.register::<Player1>();
     game.world.register::<Player2>();
@@ -308,14 +294,6 @@ pub fn start(player_image: ImageData) -> Result<(), JsValue> {
 
     let closure = Rc::new(RefCell::new(None));
     let imediate_closure = closure.clone();
-    let mut mover = TestMove;
-    engine::specs::shred::RunNow::setup(&mut mover, &mut game.world);
-    let mut mover2 = TestMove2;
-    engine::specs::shred::RunNow::setup(&mut mover2, &mut game.world);
-    let mut renderer = draw::SysRender;
-    engine::specs::shred::RunNow::setup(&mut renderer, &mut game.world);
-    let mut deb = draw::DebugCollider;
-    engine::specs::shred::RunNow::setup(&mut deb, &mut game.world);
 
     let game = Rc::new(RefCell::new(game));
 
@@ -342,13 +320,13 @@ pub fn start(player_image: ImageData) -> Result<(), JsValue> {
         closure.forget();
     }
 
+    let mut last_frame = Date::now();
     *imediate_closure.borrow_mut() = Some(Closure::wrap(Box::new(move || {
-        let mut g = game.borrow_mut();
-        mover.run_now(&mut g.world);
-        mover2.run_now(&mut g.world);
-        g.run_sys();
-        renderer.run_now(&mut g.world);
-        // deb.run_now(&mut g.world);
+        let now = Date::now();
+        let elapsed = Duration::from_secs_f64((now - last_frame).max(0.0) / 1000.0);
+        last_frame = now;
+
+        game.borrow_mut().tick(elapsed);
 
         request_animation_frame(closure.borrow().as_ref().unwrap()).unwrap();
     }) as Box<dyn FnMut()>));
@@ -364,6 +342,10 @@ pub fn start(player_image: ImageData) -> Result<(), JsValue> {
 }
 
 fn init(world: &mut World, player_image: engine::Image) {
+    let player_filter = {
+        let layers = world.read_resource::<CollisionLayers>();
+        CollisionFilter::new(layers.mask(&["player"]), layers.mask(&["world"]))
+    };
     create_block_on_grid(world, -2, 2, -1, -2);
     create_block_on_grid(world, 2, 4, -2, -3);
     create_block_on_grid(world, -5, -3, 0, -5);
@@ -373,15 +355,21 @@ fn init(world: &mut World, player_image: engine::Image) {
     world
         .create_entity()
         .with(Transform::default())
-        .with(RigidBodyBuilder::new().set_mass(10.0).build())
+        // Players stay upright, whatever pushes them.
+        .with(
+            RigidBodyBuilder::new()
+                .set_mass(10.0)
+                .inertia(f64::INFINITY)
+                .build(),
+        )
+        .with(CharacterControllerBuilder::new().jump_height(76.0).build())
         .with(
             ColliderBuilder::new()
-                .collider_type(ColliderType::Rect(14.0, 30.0))
+                .collider_type(ColliderType::Capsule(14.0, 30.0))
                 .build(),
         )
         .with(Collisions::default())
-        .with(AntiLayer1)
-        .with(Layer1)
+        .with(player_filter)
         .with(
             SpriteBuilder::new()
                 .add_image(player_image.clone())
@@ -435,15 +423,21 @@ fn init(world: &mut World, player_image: engine::Image) {
     world
         .create_entity()
         .with(Transform::default())
-        .with(RigidBodyBuilder::new().set_mass(10.0).build())
+        // Players stay upright, whatever pushes them.
+        .with(
+            RigidBodyBuilder::new()
+                .set_mass(10.0)
+                .inertia(f64::INFINITY)
+                .build(),
+        )
+        .with(CharacterControllerBuilder::new().jump_height(76.0).build())
         .with(
             ColliderBuilder::new()
-                .collider_type(ColliderType::Rect(14.0, 30.0))
+                .collider_type(ColliderType::Capsule(14.0, 30.0))
                 .build(),
         )
         .with(Collisions::default())
-        .with(AntiLayer1)
-        .with(Layer1)
+        .with(player_filter)
         .with(
             SpriteBuilder::new()
                 .add_image(player_image)
@@ -511,6 +505,10 @@ fn create_block_on_grid(world: &mut World, l: i32, r: i32, t: i32, b: i32) {
 }
 
 fn create_block(world: &mut World, x: f64, y: f64, w: f64, h: f64) {
+    let world_layer = world
+        .read_resource::<CollisionLayers>()
+        .get("world")
+        .unwrap();
     world
         .create_entity()
         .with(
@@ -523,7 +521,7 @@ fn create_block(world: &mut World, x: f64, y: f64, w: f64, h: f64) {
                 .collider_type(ColliderType::Rect(w, h))
                 .build(),
         )
-        .with(Layer1)
+        .with(CollisionFilter::new(world_layer, world_layer))
         .with(Collisions::default())
         .with(Sprite::from(vec![engine::Image::rec(
             engine::Color(176, 96, 35, 255),